@@ -8,13 +8,24 @@ TOP_DIR="${TOP_DIR:-${DIR}/..}"
 cd $TOP_DIR
 
 C_API_HEADER_NAMES=(
+  "ubidi"
   "ucal"
+  "ucnv"
   "ucol"
+  "ucsdet"
   "udat"
   "udata"
+  "udateintervalformat"
   "uenum"
+  "ufieldpositer"
+  "uldn"
   "uloc"
   "umsg"
+  "unorm2"
+  "unum"
+  "unumberformatter"
+  "uregex"
+  "ures"
   "ustring"
   "ustring"
   "utext"
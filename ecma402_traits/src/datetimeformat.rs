@@ -0,0 +1,42 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Date and time formatting, modeled after
+//! [`Intl.DateTimeFormat`](https://tc39.es/ecma402/#datetimeformat-objects).
+
+use std::fmt;
+
+/// Formats points in time, and ranges between them.
+///
+/// Points in time are given as milliseconds since the Unix epoch, which is the representation
+/// used by both ECMAScript and ICU.  How the output looks like (the locale, the fields that are
+/// shown, the time zone) is decided when the formatter is created, which is specific to each
+/// implementation.
+pub trait DateTimeFormat {
+    /// Formats `date` into `writer`.
+    ///
+    /// See `Intl.DateTimeFormat.prototype.format`.
+    fn format<W>(&self, date: f64, writer: &mut W) -> fmt::Result
+    where
+        W: fmt::Write;
+
+    /// Formats the range of time from `start` to `end` into `writer`, for example
+    /// "Jan 3 – 5, 2024".  Fields that are the same at both ends of the range are only shown
+    /// once.
+    ///
+    /// See `Intl.DateTimeFormat.prototype.formatRange`.
+    fn format_range<W>(&self, start: f64, end: f64, writer: &mut W) -> fmt::Result
+    where
+        W: fmt::Write;
+}
@@ -0,0 +1,114 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Display names of languages, regions, scripts and more, modeled after
+//! [`Intl.DisplayNames`](https://tc39.es/proposal-intl-displaynames/).
+
+use std::fmt;
+
+/// The options that can be given to [DisplayNames::try_new].
+pub mod options {
+    /// How long the display names should be.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Style {
+        /// For example "US".
+        Narrow,
+        /// For example "US".
+        Short,
+        /// For example "United States".
+        Long,
+    }
+
+    /// What kind of code the display names are requested for.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Type {
+        /// A language identifier, such as `de-AT`.
+        Language,
+        /// A region subtag, such as `AT`.
+        Region,
+        /// A script subtag, such as `Cyrl`.
+        Script,
+        /// An ISO 4217 currency code, such as `EUR`.
+        Currency,
+        /// A unicode calendar identifier, such as `gregory`.
+        Calendar,
+    }
+
+    /// What to produce when there is no display name for a code.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Fallback {
+        /// The code itself is used.
+        Code,
+        /// Nothing is produced.
+        None,
+    }
+
+    /// How language identifiers with a region are shown.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum LanguageDisplay {
+        /// Use dialect names where available, for example "Austrian German" for `de-AT`.
+        Dialect,
+        /// Always name the language and the region separately, for example "German
+        /// (Austria)" for `de-AT`.
+        Standard,
+    }
+}
+
+/// The options for creating a [DisplayNames].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Options {
+    /// See [options::Style].
+    pub style: options::Style,
+    /// See [options::Type].
+    pub in_type: options::Type,
+    /// See [options::Fallback].
+    pub fallback: options::Fallback,
+    /// See [options::LanguageDisplay].  Only used for [options::Type::Language].
+    pub language_display: options::LanguageDisplay,
+}
+
+impl Default for Options {
+    /// Gets the default options, which are the same as in ECMA 402, except that the type must
+    /// be given there.  The default type is [options::Type::Language].
+    fn default() -> Self {
+        Options {
+            style: options::Style::Long,
+            in_type: options::Type::Language,
+            fallback: options::Fallback::Code,
+            language_display: options::LanguageDisplay::Dialect,
+        }
+    }
+}
+
+/// Translates codes such as `de-AT` or `EUR` into names that can be shown to the user, such as
+/// "Österreichisches Deutsch" or "Euro" in German.
+pub trait DisplayNames {
+    /// The error reported when the display names can not be created.
+    type Error;
+
+    /// Creates display names in the language of `locale`, for the kind of code given in
+    /// `opts`.
+    fn try_new<L>(locale: L, opts: Options) -> Result<Self, Self::Error>
+    where
+        L: crate::AsBCP47,
+        Self: Sized;
+
+    /// Writes the display name of `code` into `writer`.  If there is no display name for
+    /// `code`, and the fallback is [options::Fallback::None], nothing is written.
+    ///
+    /// See `Intl.DisplayNames.prototype.of`.
+    fn of<W>(&self, code: &str, writer: &mut W) -> fmt::Result
+    where
+        W: fmt::Write;
+}
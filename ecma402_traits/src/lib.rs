@@ -0,0 +1,84 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Traits for ECMA 402 style internationalization APIs
+//!
+//! This crate contains the type traits that describe the API surface of the [ECMA 402
+//! specification](https://www.ecma-international.org/publications/standards/Ecma-402.htm),
+//! without tying it to a specific implementation.  The crates in this repository provide an
+//! implementation based on ICU, but nothing here depends on ICU.
+//!
+//! Formatters are expected to accept their locale as any type that implements [AsBCP47], so that
+//! the caller can use whatever locale type is most convenient for them.
+//!
+//! ## Examples
+//!
+//! ```
+//! use ecma402_traits::{AsBCP47, LanguageIdentifier};
+//!
+//! struct Fixed;
+//!
+//! impl AsBCP47 for Fixed {
+//!     fn as_bcp47(&self) -> String {
+//!         "sr-Cyrl-RS".to_string()
+//!     }
+//! }
+//!
+//! impl LanguageIdentifier for Fixed {
+//!     fn language(&self) -> Option<String> {
+//!         Some("sr".to_string())
+//!     }
+//!     fn script(&self) -> Option<String> {
+//!         Some("Cyrl".to_string())
+//!     }
+//!     fn region(&self) -> Option<String> {
+//!         Some("RS".to_string())
+//!     }
+//! }
+//!
+//! fn tag(locale: impl AsBCP47) -> String {
+//!     locale.as_bcp47()
+//! }
+//! assert_eq!("sr-Cyrl-RS", tag(&Fixed));
+//! ```
+
+pub mod datetimeformat;
+pub mod displaynames;
+
+/// A type that can be converted into a [BCP 47](https://tools.ietf.org/html/bcp47) language tag,
+/// such as `sr-Cyrl-RS` or `en-US-u-hc-h23`.
+pub trait AsBCP47 {
+    /// Returns the language tag for this value.
+    fn as_bcp47(&self) -> String;
+}
+
+impl<T: AsBCP47 + ?Sized> AsBCP47 for &T {
+    fn as_bcp47(&self) -> String {
+        (**self).as_bcp47()
+    }
+}
+
+/// The basic subtags of a language identifier.
+///
+/// Each of the subtags is optional, in which case `None` is returned.
+pub trait LanguageIdentifier: AsBCP47 {
+    /// Returns the language subtag, such as `sr`.
+    fn language(&self) -> Option<String>;
+
+    /// Returns the script subtag, such as `Cyrl`.
+    fn script(&self) -> Option<String>;
+
+    /// Returns the region subtag, such as `RS`.
+    fn region(&self) -> Option<String>;
+}
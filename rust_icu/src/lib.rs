@@ -27,26 +27,50 @@
 //!
 //! | Original | Remapped |
 //! | -------- | -------- |
+//! | rust_icu_cache | icu::cache |
 //! | rust_icu_common | icu::common |
 //! | rust_icu_sys | icu::sys |
+//! | rust_icu_ubidi | icu::bidi |
 //! | rust_icu_ucal | icu::cal |
+//! | rust_icu_ucnv | icu::cnv |
 //! | rust_icu_ucol | icu::col |
+//! | rust_icu_ucsdet | icu::csdet |
 //! | rust_icu_udat | icu::dat |
 //! | rust_icu_udata | icu::data |
+//! | rust_icu_udateintervalformat | icu::dateintervalformat |
 //! | rust_icu_uenum | icu::enums |
+//! | rust_icu_ufieldpositer | icu::fieldpositer |
+//! | rust_icu_uldn | icu::ldn |
 //! | rust_icu_uloc | icu::loc |
 //! | rust_icu_umsg | icu::msg |
+//! | rust_icu_unorm2 | icu::norm |
+//! | rust_icu_unum | icu::num |
+//! | rust_icu_unumberformatter | icu::numberformatter |
+//! | rust_icu_uregex | icu::regex |
+//! | rust_icu_ures | icu::res |
 //! | rust_icu_ustring | icu::string |
 //! | rust_icu_utext | text |
 
+pub use rust_icu_cache as cache;
 pub use rust_icu_common as common;
 pub use rust_icu_sys as sys;
+pub use rust_icu_ubidi as bidi;
 pub use rust_icu_ucal as cal;
+pub use rust_icu_ucnv as cnv;
 pub use rust_icu_ucol as col;
+pub use rust_icu_ucsdet as csdet;
 pub use rust_icu_udat as dat;
 pub use rust_icu_udata as data;
+pub use rust_icu_udateintervalformat as dateintervalformat;
 pub use rust_icu_uenum as enums;
+pub use rust_icu_ufieldpositer as fieldpositer;
+pub use rust_icu_uldn as ldn;
 pub use rust_icu_uloc as loc;
 pub use rust_icu_umsg as msg;
+pub use rust_icu_unorm2 as norm;
+pub use rust_icu_unum as num;
+pub use rust_icu_unumberformatter as numberformatter;
+pub use rust_icu_uregex as regex;
+pub use rust_icu_ures as res;
 pub use rust_icu_ustring as string;
 pub use rust_icu_utext as text;
@@ -0,0 +1,361 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # A thread-safe cache of ICU formatters
+//!
+//! Opening a formatter such as `UDateFormat`, `UNumberFormat` or `UCollator` requires ICU to
+//! load and process the locale data, which is expensive compared to using the formatter.
+//! Cloning an existing formatter is much cheaper.  [Cache] keeps one formatter for each key,
+//! for example the locale and the pattern, and hands out clones of it.  The clones are made with
+//! [TryClone], so that a failure to allocate a clone is reported as an error.
+//!
+//! ## Thread safety
+//!
+//! The ICU formatters may be moved between threads (they are `Send`), but may not be used from
+//! several threads at the same time (they are not `Sync`).  The cache only ever touches the
+//! formatters it keeps while holding its lock, and each caller gets its own clone.  This makes a
+//! [Cache] `Send` and `Sync` whenever its keys and formatters are `Send`, so it can be shared
+//! between threads, for example in a `static` or in an `Arc`.  Formatter types that are not
+//! `Send` can not be put into a shared cache, which the compiler enforces.
+//!
+//! ## Example
+//!
+//! ```
+//! use {
+//!     rust_icu_cache as cache, rust_icu_common as common, rust_icu_udat as udat,
+//!     rust_icu_uloc as uloc, rust_icu_ustring as ustring, std::convert::TryFrom,
+//! };
+//!
+//! let formats: cache::Cache<(String, String), udat::UDateFormat> = cache::Cache::new(100);
+//! let format = formats.get_or_try_insert_with(
+//!     ("de-DE".to_string(), "d. MMMM yyyy".to_string()),
+//!     || -> Result<_, common::Error> {
+//!         let loc = uloc::ULoc::try_from("de-DE")?;
+//!         let tz_id = ustring::UChar::try_from("Europe/Berlin")?;
+//!         let pattern = ustring::UChar::try_from("d. MMMM yyyy")?;
+//!         udat::UDateFormat::new_with_pattern(&loc, &tz_id, &pattern)
+//!     },
+//! ).expect("date format");
+//! assert_eq!("2. März 2020", format.format(1583170200000.0).expect("formatted"));
+//! ```
+
+use {
+    rust_icu_common as common, rust_icu_ucol as ucol, rust_icu_udat as udat, rust_icu_unum as unum,
+    std::{
+        collections::HashMap,
+        hash::Hash,
+        sync::{Mutex, MutexGuard},
+    },
+};
+
+/// A formatter that can be copied, where making the copy may fail.
+pub trait TryClone: Sized {
+    /// Makes an independent copy of `self`.
+    fn try_clone(&self) -> Result<Self, common::Error>;
+}
+
+impl TryClone for udat::UDateFormat {
+    fn try_clone(&self) -> Result<Self, common::Error> {
+        udat::UDateFormat::try_clone(self)
+    }
+}
+
+impl TryClone for unum::UNumberFormat {
+    fn try_clone(&self) -> Result<Self, common::Error> {
+        unum::UNumberFormat::try_clone(self)
+    }
+}
+
+impl TryClone for ucol::UCollator {
+    fn try_clone(&self) -> Result<Self, common::Error> {
+        ucol::UCollator::try_clone(self)
+    }
+}
+
+/// A thread-safe cache of formatters, keyed by `K`, which hands out clones of the formatters
+/// it holds.
+///
+/// When the cache is full, adding a formatter evicts the one that was used least recently.
+pub struct Cache<K, V> {
+    capacity: usize,
+    inner: Mutex<Inner<K, V>>,
+}
+
+struct Inner<K, V> {
+    entries: HashMap<K, Entry<V>>,
+    // Counts the accesses to the cache.  Each entry remembers the count of its last access.
+    clock: u64,
+}
+
+struct Entry<V> {
+    value: V,
+    last_used: u64,
+}
+
+impl<K, V> Inner<K, V>
+where
+    K: Eq + Hash,
+    V: TryClone,
+{
+    // Returns a clone of the value for `key`, if any, and marks it as most recently used.
+    fn get(&mut self, key: &K) -> Result<Option<V>, common::Error> {
+        self.clock += 1;
+        let clock = self.clock;
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                entry.last_used = clock;
+                entry.value.try_clone().map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    // Adds `value` for `key`, evicting the least recently used entry if there is no room.
+    fn insert(&mut self, key: K, value: V, capacity: usize) {
+        if !self.entries.contains_key(&key) && self.entries.len() >= capacity {
+            // No two entries have the same access count, so exactly one entry is removed.  This
+            // is linear in the size of the cache, but happens only after a new formatter was
+            // made, which costs much more.
+            if let Some(oldest) = self.entries.values().map(|entry| entry.last_used).min() {
+                self.entries.retain(|_, entry| entry.last_used != oldest);
+            }
+        }
+        self.clock += 1;
+        let last_used = self.clock;
+        self.entries.insert(key, Entry { value, last_used });
+    }
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Eq + Hash,
+    V: TryClone + Send,
+{
+    /// Creates an empty cache that holds at most `capacity` formatters.
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "programmer error: the capacity must not be zero"
+        );
+        Cache {
+            capacity,
+            inner: Mutex::new(Inner {
+                entries: HashMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    /// Returns a clone of the formatter for `key`.  If there is none, `make` is called to create
+    /// one, which is kept in the cache and a clone of which is returned.
+    ///
+    /// Errors from `make` are returned as they are, and nothing is added to the cache in that
+    /// case.  `make` is called without holding the lock of the cache, so threads asking for
+    /// other formatters are not held up while a new one is made.  An error is also returned,
+    /// and nothing added, if the new formatter can not be cloned.
+    pub fn get_or_try_insert_with<F, E>(&self, key: K, make: F) -> Result<V, E>
+    where
+        F: FnOnce() -> Result<V, E>,
+        E: From<common::Error>,
+    {
+        if let Some(value) = self.lock().get(&key)? {
+            return Ok(value);
+        }
+        let value = make()?;
+        let result = value.try_clone()?;
+        self.lock().insert(key, value, self.capacity);
+        Ok(result)
+    }
+
+    /// Returns a clone of the formatter for `key`, if there is one in the cache.
+    ///
+    /// Returns an error if the formatter can not be cloned.
+    pub fn get(&self, key: &K) -> Result<Option<V>, common::Error> {
+        self.lock().get(key)
+    }
+
+    /// Adds `value` to the cache as the formatter for `key`, replacing any formatter that was
+    /// there before.
+    pub fn insert(&self, key: K, value: V) {
+        self.lock().insert(key, value, self.capacity);
+    }
+
+    /// Returns the number of formatters in the cache.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns true if there are no formatters in the cache.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the largest number of formatters that the cache holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Removes all formatters from the cache.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    // The entries are consistent between any two calls on the cache, so a panic in another
+    // thread, for example in `make`, does not make the cache unusable.
+    fn lock(&self) -> MutexGuard<'_, Inner<K, V>> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        rust_icu_sys as sys, rust_icu_uloc as uloc, rust_icu_ustring as ustring,
+        std::{convert::TryFrom, sync::Arc, thread},
+    };
+
+    // A stand-in for a formatter, which fails to clone if it is negative.
+    #[derive(Debug, PartialEq)]
+    struct Value(i32);
+
+    impl TryClone for Value {
+        fn try_clone(&self) -> Result<Self, common::Error> {
+            if self.0 < 0 {
+                return Err(common::Error::Sys {
+                    function: "Value::try_clone",
+                    code: sys::UErrorCode::U_MEMORY_ALLOCATION_ERROR,
+                });
+            }
+            Ok(Value(self.0))
+        }
+    }
+
+    // Fails to compile if a cache of the formatter type `V` can not be shared between threads.
+    fn assert_shareable<V: TryClone + Send>() {
+        fn is_send_and_sync<T: Send + Sync>() {}
+        is_send_and_sync::<Cache<String, V>>();
+    }
+
+    #[test]
+    fn formatter_caches_are_shareable() {
+        assert_shareable::<udat::UDateFormat>();
+        assert_shareable::<unum::UNumberFormat>();
+        assert_shareable::<ucol::UCollator>();
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() -> Result<(), common::Error> {
+        let cache: Cache<&str, Value> = Cache::new(2);
+        cache.insert("a", Value(1));
+        cache.insert("b", Value(2));
+        assert_eq!(Some(Value(1)), cache.get(&"a")?);
+        cache.insert("c", Value(3));
+
+        assert_eq!(2, cache.len());
+        assert_eq!(None, cache.get(&"b")?);
+        assert_eq!(Some(Value(1)), cache.get(&"a")?);
+        assert_eq!(Some(Value(3)), cache.get(&"c")?);
+
+        cache.insert("a", Value(4));
+        assert_eq!(2, cache.len());
+        assert_eq!(Some(Value(4)), cache.get(&"a")?);
+        Ok(())
+    }
+
+    #[test]
+    fn errors_are_not_cached() -> Result<(), common::Error> {
+        let cache: Cache<&str, Value> = Cache::new(2);
+        let nope = || common::Error::Sys {
+            function: "make",
+            code: sys::UErrorCode::U_ILLEGAL_ARGUMENT_ERROR,
+        };
+        assert!(cache.get_or_try_insert_with("a", || Err(nope())).is_err());
+        assert!(cache.is_empty());
+        assert_eq!(
+            Value(1),
+            cache.get_or_try_insert_with("a", || Ok::<_, common::Error>(Value(1)))?
+        );
+        assert_eq!(
+            Value(1),
+            cache.get_or_try_insert_with("a", || -> Result<Value, common::Error> {
+                panic!("must not be called for a cached value")
+            })?
+        );
+        cache.clear();
+        assert!(cache.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn clone_errors_are_returned() {
+        let cache: Cache<&str, Value> = Cache::new(2);
+        assert!(cache
+            .get_or_try_insert_with("a", || Ok::<_, common::Error>(Value(-1)))
+            .is_err());
+        assert!(cache.is_empty());
+
+        cache.insert("a", Value(-1));
+        assert!(cache.get(&"a").is_err());
+    }
+
+    #[test]
+    fn shared_number_formats() -> Result<(), common::Error> {
+        let cache: Arc<Cache<(String, String), unum::UNumberFormat>> = Arc::new(Cache::new(10));
+        let threads: Vec<_> = ["de-DE", "en-US", "de-DE", "en-US"]
+            .iter()
+            .map(|locale| {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    let key = (locale.to_string(), "#,##0.00".to_string());
+                    let fmt = cache.get_or_try_insert_with(key, || {
+                        let loc = uloc::ULoc::try_from(*locale)?;
+                        unum::UNumberFormat::try_new_decimal_pattern("#,##0.00", &loc)
+                    })?;
+                    fmt.format_f64(1234.5)
+                })
+            })
+            .collect();
+        let formatted = threads
+            .into_iter()
+            .map(|t| t.join().expect("thread finished"))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            vec!["1.234,50", "1,234.50", "1.234,50", "1,234.50"],
+            formatted
+        );
+        assert_eq!(2, cache.len());
+        Ok(())
+    }
+
+    #[test]
+    fn cached_date_format() -> Result<(), common::Error> {
+        let cache: Cache<String, udat::UDateFormat> = Cache::new(10);
+        let make = || -> Result<_, common::Error> {
+            let loc = uloc::ULoc::try_from("en-US")?;
+            let tz_id = ustring::UChar::try_from("America/New_York")?;
+            let pattern = ustring::UChar::try_from("yyyy-MM-dd")?;
+            udat::UDateFormat::new_with_pattern(&loc, &tz_id, &pattern)
+        };
+        let first = cache.get_or_try_insert_with("en-US".to_string(), make)?;
+        let second = cache.get_or_try_insert_with("en-US".to_string(), make)?;
+        assert_eq!("2020-03-02", first.format(1583170200000.0)?);
+        assert_eq!("2020-03-02", second.format(1583170200000.0)?);
+        Ok(())
+    }
+}
@@ -26,16 +26,67 @@ use {
 ///
 /// This is modeled after absl::Status in the Abseil library, which provides ways
 /// for users to avoid dealing with all the numerous error codes directly.
+///
+/// Only failures are reported as errors.  ICU also reports warnings, such as
+/// `U_USING_DEFAULT_WARNING` when a locale falls back to the root locale; those do not stop the
+/// operation, and are not errors.  Wrappers that open data which may come from another locale
+/// keep the warning for their callers, see [Error::ok_with_warning].
+///
+/// `U_BUFFER_OVERFLOW_ERROR` is a failure like any other.  The wrappers that ask ICU for the size
+/// of a buffer ("preflighting") handle it internally, so that it does not reach their callers,
+/// but [Error::ok_or_warning] reports it.
+///
+/// Errors reported by ICU carry the name of the ICU function that failed, as well as the ICU
+/// error code.  The common cases that a caller may want to recover from have their own
+/// variants.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
-    /// The error originating in the underlying sys library.
-    ///
-    /// At the moment it is possible to produce an Error which has a zero error code (i.e. no
-    /// error), because it makes it unnecessary for users to deal with error codes directly.  It
-    /// does make for a bit weird API, so we may turn it around a bit.  Ideally, it should not be
-    /// possible to have an Error that isn't really an error.
-    #[error("ICU error code: {}", _0)]
-    Sys(sys::UErrorCode),
+    /// A failure reported by an ICU function, that does not fall into any of the more specific
+    /// categories below.
+    #[error("{function}: ICU error code: {code}")]
+    Sys {
+        /// The ICU function that failed, such as `ucal_open`.
+        function: &'static str,
+        /// The error code reported by ICU.  This is always a failure, never a warning.
+        code: sys::UErrorCode,
+    },
+
+    /// The locale `locale` could not be used, for example because it is not a well formed
+    /// language tag.
+    #[error("{function}: invalid locale: {locale:?}: ICU error code: {code}")]
+    InvalidLocale {
+        /// The ICU function that failed, such as `uloc_forLanguageTag`.
+        function: &'static str,
+        /// The error code reported by ICU.
+        code: sys::UErrorCode,
+        /// The locale that was rejected.
+        locale: String,
+    },
+
+    /// Data that was asked for, such as a resource bundle, does not exist.
+    #[error("{function}: missing resource: ICU error code: {code}")]
+    MissingResource {
+        /// The ICU function that failed, such as `ures_open`.
+        function: &'static str,
+        /// The error code reported by ICU, `U_MISSING_RESOURCE_ERROR`.
+        code: sys::UErrorCode,
+    },
+
+    /// A pattern, such as a number format pattern or a regular expression, could not be
+    /// parsed.
+    #[error("{function}: parse error at line {line:?}, offset {offset:?}: ICU error code: {code}")]
+    Parse {
+        /// The ICU function that failed, such as `uregex_open`.
+        function: &'static str,
+        /// The error code reported by ICU.
+        code: sys::UErrorCode,
+        /// The line of the pattern at which parsing failed, if ICU reported it.
+        line: Option<usize>,
+        /// The offset at which parsing failed, within the line if there is one, or else within
+        /// the entire pattern, if ICU reported it.  Offsets are in UTF-16 code units.
+        offset: Option<usize>,
+    },
 
     /// Errors originating from the wrapper code.  For example when pre-converting input into
     /// UTF8 for input that happens to be malformed.
@@ -52,18 +103,44 @@ impl Error {
         code == Self::OK_CODE
     }
 
-    /// Creates a new error from the supplied status.  Ok is returned if the error code does not
-    /// correspond to an error code (as opposed to OK or a warning code).
-    pub fn ok_or_warning(status: sys::UErrorCode) -> Result<(), Self> {
-        if Self::is_ok(status) || status < Self::OK_CODE {
-            Ok(())
+    /// Returns true if this error code is a warning.  Warnings do not prevent an operation from
+    /// completing.
+    pub fn is_warning(code: sys::UErrorCode) -> bool {
+        code < Self::OK_CODE
+    }
+
+    /// Returns true if this error code is a failure.
+    pub fn is_failure(code: sys::UErrorCode) -> bool {
+        code > Self::OK_CODE
+    }
+
+    /// Creates a new error from the supplied status, reported by the ICU function `function`.
+    /// Ok is returned if the error code does not correspond to an error code (as opposed to OK
+    /// or a warning code).
+    pub fn ok_or_warning(function: &'static str, status: sys::UErrorCode) -> Result<(), Self> {
+        if Self::is_failure(status) {
+            Err(Error::from_code(function, status))
         } else {
-            Err(Error::Sys(status))
+            Ok(())
         }
     }
 
-    /// Creates a new error from the supplied status.  Ok is returned if the
-    /// error code does not constitute an error in preflight mode.
+    /// Like [Error::ok_or_warning], but also returns the warning that ICU reported in `status`,
+    /// if any, so that it can be passed on to the caller.
+    pub fn ok_with_warning(
+        function: &'static str,
+        status: sys::UErrorCode,
+    ) -> Result<Option<sys::UErrorCode>, Self> {
+        Self::ok_or_warning(function, status)?;
+        Ok(if Self::is_warning(status) {
+            Some(status)
+        } else {
+            None
+        })
+    }
+
+    /// Creates a new error from the supplied status, reported by the ICU function `function`.
+    /// Ok is returned if the error code does not constitute an error in preflight mode.
     ///
     /// This error check explicitly ignores the buffer overflow error when reporting whether it
     /// contains an error condition.
@@ -73,29 +150,143 @@ impl Error {
     /// this call is made is to offer a zero-capacity buffer (which could be pointed to by a `NULL`
     /// pointer), and then call the respective function.  The function will compute the buffer
     /// size, but will also return a bogus buffer overflow error.
-    pub fn ok_preflight(status: sys::UErrorCode) -> Result<(), Self> {
-        if status > Self::OK_CODE && status != sys::UErrorCode::U_BUFFER_OVERFLOW_ERROR {
-            Err(Error::Sys(status))
-        } else {
+    pub fn ok_preflight(function: &'static str, status: sys::UErrorCode) -> Result<(), Self> {
+        if status == sys::UErrorCode::U_BUFFER_OVERFLOW_ERROR {
             Ok(())
+        } else {
+            Self::ok_or_warning(function, status)
+        }
+    }
+
+    /// Creates a new error from the supplied status, reported by the ICU function `function`,
+    /// which also reported the location of a failure to parse its input in `parse_error`.  Ok is
+    /// returned if the error code is not a failure.
+    pub fn ok_or_parse_error(
+        function: &'static str,
+        status: sys::UErrorCode,
+        parse_error: &sys::UParseError,
+    ) -> Result<(), Self> {
+        if !Self::is_failure(status) {
+            return Ok(());
+        }
+        if !Self::is_parse_code(status) && !Self::is_regex_code(status) {
+            return Err(Self::from_code(function, status));
+        }
+        // ICU uses -1 for locations that it does not know.
+        let location = |value: i32| {
+            if value >= 0 {
+                Some(value as usize)
+            } else {
+                None
+            }
+        };
+        Err(Error::Parse {
+            function,
+            code: status,
+            line: location(parse_error.line),
+            offset: location(parse_error.offset),
+        })
+    }
+
+    /// Turns an error reported for a locale argument into [Error::InvalidLocale].  Other kinds
+    /// of errors are returned unchanged.
+    pub fn for_locale(self, locale: &str) -> Self {
+        match self {
+            Error::Sys { function, code } => Error::InvalidLocale {
+                function,
+                code,
+                locale: locale.to_string(),
+            },
+            other => other,
+        }
+    }
+
+    // Classifies a failure code into one of the error categories.  Parse errors found here do
+    // not have a location.
+    fn from_code(function: &'static str, code: sys::UErrorCode) -> Self {
+        if code == sys::UErrorCode::U_MISSING_RESOURCE_ERROR {
+            Error::MissingResource { function, code }
+        } else if Self::is_parse_code(code) {
+            Error::Parse {
+                function,
+                code,
+                line: None,
+                offset: None,
+            }
+        } else {
+            Error::Sys { function, code }
+        }
+    }
+
+    // Returns true for the error codes that report malformed patterns and rules.  The other
+    // formatting errors, such as an argument of the wrong type, are not about the syntax.
+    fn is_parse_code(code: sys::UErrorCode) -> bool {
+        use sys::UErrorCode::*;
+        let in_range = |start: sys::UErrorCode, limit: sys::UErrorCode| {
+            code as i32 >= start as i32 && (code as i32) < limit as i32
+        };
+        code == U_PARSE_ERROR
+            || code == U_MESSAGE_PARSE_ERROR
+            || in_range(U_BAD_VARIABLE_DEFINITION, U_PARSE_ERROR_LIMIT)
+            || matches!(
+                code,
+                U_UNEXPECTED_TOKEN
+                    | U_MULTIPLE_DECIMAL_SEPARATORS
+                    | U_MULTIPLE_EXPONENTIAL_SYMBOLS
+                    | U_MALFORMED_EXPONENTIAL_PATTERN
+                    | U_MULTIPLE_PERCENT_SYMBOLS
+                    | U_MULTIPLE_PERMILL_SYMBOLS
+                    | U_MULTIPLE_PAD_SPECIFIERS
+                    | U_PATTERN_SYNTAX_ERROR
+                    | U_UNMATCHED_BRACES
+                    | U_DUPLICATE_KEYWORD
+                    | U_UNDEFINED_KEYWORD
+                    | U_DEFAULT_KEYWORD_MISSING
+                    | U_NUMBER_SKELETON_SYNTAX_ERROR
+            )
+    }
+
+    // Returns true for the error codes of regular expressions.  Most of them report malformed
+    // patterns, but some are reported while matching.
+    fn is_regex_code(code: sys::UErrorCode) -> bool {
+        use sys::UErrorCode::*;
+        code as i32 >= U_REGEX_INTERNAL_ERROR as i32 && (code as i32) < U_REGEX_ERROR_LIMIT as i32
+    }
+
+    /// Returns the error code reported by ICU, if this error was reported by ICU.
+    pub fn code(&self) -> Option<sys::UErrorCode> {
+        match self {
+            Error::Sys { code, .. }
+            | Error::InvalidLocale { code, .. }
+            | Error::MissingResource { code, .. }
+            | Error::Parse { code, .. } => Some(*code),
+            Error::Wrapper(_) => None,
+        }
+    }
+
+    /// Returns the name of the ICU function that failed, if this error was reported by ICU.
+    pub fn function(&self) -> Option<&'static str> {
+        match self {
+            Error::Sys { function, .. }
+            | Error::InvalidLocale { function, .. }
+            | Error::MissingResource { function, .. }
+            | Error::Parse { function, .. } => Some(function),
+            Error::Wrapper(_) => None,
         }
     }
 
     /// Returns true if this error has the supplied `code`.
     pub fn is_code(&self, code: sys::UErrorCode) -> bool {
-        if let Error::Sys(c) = self {
-            return *c == code;
-        }
-        false
+        self.code() == Some(code)
     }
 
     /// Returns true if the error is an error, not a warning.
     ///
     /// The ICU4C library has error codes for errors and warnings.
     pub fn is_err(&self) -> bool {
-        match self {
-            Error::Sys(code) => *code > sys::UErrorCode::U_ZERO_ERROR,
-            Error::Wrapper(_) => true,
+        match self.code() {
+            Some(code) => Self::is_failure(code),
+            None => true,
         }
     }
 
@@ -103,22 +294,18 @@ impl Error {
     ///
     /// This error check explicitly ignores the buffer overflow error when reporting whether it
     /// contains an error condition.
-    ///
-    /// Preflight calls to ICU libraries do a dummy scan of the input to determine the buffer sizes
-    /// required on the output in case of conversion calls such as `ucal_strFromUTF8`.  The way
-    /// this call is made is to offer a zero-capacity buffer (which could be pointed to by a `NULL`
-    /// pointer), and then call the respective function.  The function will compute the buffer
-    /// size, but will also return a bogus buffer overflow error.
+    #[deprecated(note = "use Error::ok_preflight on the status code instead")]
     pub fn is_preflight_err(&self) -> bool {
         // We may expand the set of error codes that are exempt from error checks in preflight.
         self.is_err() && !self.is_code(sys::UErrorCode::U_BUFFER_OVERFLOW_ERROR)
     }
 
     /// Returns true if the error is, in fact, a warning (nonfatal).
+    #[deprecated(note = "errors never hold a warning, so this is always false")]
     pub fn is_warn(&self) -> bool {
-        match self {
-            Error::Sys(c) => *c < sys::UErrorCode::U_ZERO_ERROR,
-            _ => false,
+        match self.code() {
+            Some(code) => Self::is_warning(code),
+            None => false,
         }
     }
 
@@ -168,6 +355,7 @@ impl From<std::string::FromUtf8Error> for Error {
 ///
 /// ```ignore
 /// fn my_method(
+///     function: &'static str,
 ///     method_to_call: unsafe extern "C" fn(
 ///         before_type_a,
 ///         before_type_b,
@@ -183,6 +371,9 @@ impl From<std::string::FromUtf8Error> for Error {
 ///     after_arg_b: after_type_b
 /// ) -> Result<String, common::Error> {}
 /// ```
+///
+/// where `function` is the name of the ICU function that `method_to_call` points to, which is
+/// reported in errors.
 #[macro_export]
 macro_rules! buffered_string_method_with_retry {
 
@@ -190,6 +381,7 @@ macro_rules! buffered_string_method_with_retry {
      [$($before_arg:ident: $before_arg_type:ty,)*],
      [$($after_arg:ident: $after_arg_type:ty,)*]) => {
         fn $method_name(
+            function: &'static str,
             method_to_call: unsafe extern "C" fn(
                 $($before_arg_type,)*
                 *mut raw::c_char,
@@ -243,7 +435,7 @@ macro_rules! buffered_string_method_with_retry {
                 };
             }
 
-            common::Error::ok_or_warning(status)?;
+            common::Error::ok_or_warning(function, status)?;
 
             // Adjust the size of the buffer here.
             if (full_len >= 0) {
@@ -257,6 +449,106 @@ macro_rules! buffered_string_method_with_retry {
     }
 }
 
+/// Generates a method to wrap ICU4C methods that require a resizable output `UChar` buffer.
+///
+/// This is the same as `buffered_string_method_with_retry`, except that the output buffer is
+/// a `*mut sys::UChar`, and the generated method returns a `ustring::UChar`.  The invoking
+/// crate must have `rust_icu_ustring` in scope as `ustring`.
+///
+/// For an invocation of the form
+///
+/// ```ignore
+/// buffered_uchar_method_with_retry!(
+///     my_method,
+///     BUFFER_CAPACITY,
+///     [before_arg_a: before_type_a,],
+///     [after_arg_a: after_type_a,]
+/// );
+/// ```
+///
+/// the generated method has a signature of the form
+///
+/// ```ignore
+/// fn my_method(
+///     function: &'static str,
+///     method_to_call: unsafe extern "C" fn(
+///         before_type_a,
+///         *mut sys::UChar,
+///         i32,
+///         after_type_a,
+///         *mut sys::UErrorCode,
+///     ) -> i32,
+///     before_arg_a: before_type_a,
+///     after_arg_a: after_type_a,
+/// ) -> Result<ustring::UChar, common::Error> {}
+/// ```
+///
+/// where `function` is the name of the ICU function that `method_to_call` points to, which is
+/// reported in errors.
+#[macro_export]
+macro_rules! buffered_uchar_method_with_retry {
+
+    ($method_name:ident, $buffer_capacity:expr,
+     [$($before_arg:ident: $before_arg_type:ty,)*],
+     [$($after_arg:ident: $after_arg_type:ty,)*]) => {
+        fn $method_name(
+            function: &'static str,
+            method_to_call: unsafe extern "C" fn(
+                $($before_arg_type,)*
+                *mut sys::UChar,
+                i32,
+                $($after_arg_type,)*
+                *mut sys::UErrorCode,
+            ) -> i32,
+            $($before_arg: $before_arg_type,)*
+            $($after_arg: $after_arg_type,)*
+        ) -> Result<ustring::UChar, common::Error> {
+            let mut status = common::Error::OK_CODE;
+            let mut buf = ustring::UChar::new_with_capacity($buffer_capacity);
+
+            // Requires that any pointers that are passed in are valid.
+            let full_len: i32 = unsafe {
+                assert!(common::Error::is_ok(status));
+                method_to_call(
+                    $($before_arg,)*
+                    buf.as_mut_c_ptr(),
+                    $buffer_capacity as i32,
+                    $($after_arg,)*
+                    &mut status,
+                )
+            };
+
+            if status == sys::UErrorCode::U_BUFFER_OVERFLOW_ERROR ||
+               (common::Error::is_ok(status) && full_len > $buffer_capacity as i32) {
+
+                assert!(full_len > 0);
+                buf.resize(full_len as usize);
+
+                // Same unsafe requirements as above, plus full_len must be exactly the output
+                // buffer size.
+                status = common::Error::OK_CODE;
+                unsafe {
+                    method_to_call(
+                        $($before_arg,)*
+                        buf.as_mut_c_ptr(),
+                        full_len,
+                        $($after_arg,)*
+                        &mut status,
+                    )
+                };
+            }
+
+            common::Error::ok_or_warning(function, status)?;
+
+            // Adjust the size of the buffer here.
+            if full_len >= 0 {
+                buf.resize(full_len as usize);
+            }
+            Ok(buf)
+        }
+    }
+}
+
 /// Used to simulate an array of C-style strings.
 #[derive(Debug)]
 pub struct CStringVec {
@@ -304,12 +596,103 @@ mod tests {
 
     #[test]
     fn test_error_code() {
-        let error = Error::ok_or_warning(sys::UErrorCode::U_BUFFER_OVERFLOW_ERROR)
+        let error = Error::ok_or_warning("u_strToUTF8", sys::UErrorCode::U_BUFFER_OVERFLOW_ERROR)
             .err()
             .unwrap();
         assert!(error.is_code(sys::UErrorCode::U_BUFFER_OVERFLOW_ERROR));
-        assert!(!error.is_preflight_err());
         assert!(!error.is_code(sys::UErrorCode::U_ZERO_ERROR));
+        assert_eq!(Some("u_strToUTF8"), error.function());
+    }
+
+    #[test]
+    fn test_ok_with_warning() {
+        assert_eq!(
+            Some(sys::UErrorCode::U_USING_DEFAULT_WARNING),
+            Error::ok_with_warning("ures_open", sys::UErrorCode::U_USING_DEFAULT_WARNING)
+                .expect("not an error")
+        );
+        assert_eq!(
+            None,
+            Error::ok_with_warning("ures_open", sys::UErrorCode::U_ZERO_ERROR)
+                .expect("not an error")
+        );
+        assert!(
+            Error::ok_with_warning("ures_open", sys::UErrorCode::U_MISSING_RESOURCE_ERROR).is_err()
+        );
+    }
+
+    #[test]
+    fn test_warnings_are_not_errors() {
+        assert!(
+            Error::ok_or_warning("ures_open", sys::UErrorCode::U_USING_DEFAULT_WARNING).is_ok()
+        );
+        assert!(
+            Error::ok_preflight("u_strToUTF8", sys::UErrorCode::U_BUFFER_OVERFLOW_ERROR).is_ok()
+        );
+        assert!(Error::is_warning(sys::UErrorCode::U_USING_FALLBACK_WARNING));
+        assert!(!Error::is_failure(
+            sys::UErrorCode::U_USING_FALLBACK_WARNING
+        ));
+    }
+
+    #[test]
+    fn test_error_categories() {
+        let error = Error::ok_or_warning("ures_open", sys::UErrorCode::U_MISSING_RESOURCE_ERROR);
+        assert!(matches!(
+            error,
+            Err(Error::MissingResource {
+                function: "ures_open",
+                ..
+            })
+        ));
+
+        let error = Error::ok_or_warning("umsg_open", sys::UErrorCode::U_PATTERN_SYNTAX_ERROR);
+        assert!(matches!(
+            error,
+            Err(Error::Parse {
+                line: None,
+                offset: None,
+                ..
+            })
+        ));
+
+        let error = Error::ok_or_warning("umsg_format", sys::UErrorCode::U_ARGUMENT_TYPE_MISMATCH);
+        assert!(matches!(
+            error,
+            Err(Error::Sys {
+                function: "umsg_format",
+                code: sys::UErrorCode::U_ARGUMENT_TYPE_MISMATCH,
+            })
+        ));
+
+        let parse_error = sys::UParseError {
+            line: -1,
+            offset: 3,
+            ..Default::default()
+        };
+        let error = Error::ok_or_parse_error(
+            "uregex_open",
+            sys::UErrorCode::U_REGEX_MISMATCHED_PAREN,
+            &parse_error,
+        );
+        assert!(matches!(
+            error,
+            Err(Error::Parse {
+                function: "uregex_open",
+                code: sys::UErrorCode::U_REGEX_MISMATCHED_PAREN,
+                line: None,
+                offset: Some(3),
+            })
+        ));
+
+        let error = Error::ok_or_warning(
+            "uloc_forLanguageTag",
+            sys::UErrorCode::U_ILLEGAL_ARGUMENT_ERROR,
+        )
+        .unwrap_err()
+        .for_locale("en-");
+        assert!(matches!(error, Error::InvalidLocale { ref locale, .. } if locale == "en-"));
+        assert!(error.is_code(sys::UErrorCode::U_ILLEGAL_ARGUMENT_ERROR));
     }
 
     #[test]
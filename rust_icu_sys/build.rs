@@ -36,7 +36,8 @@ lazy_static! {
     // headers.  Any of these will fail if the required binaries are not present in $PATH.
     static ref BINDGEN_SOURCE_MODULES: Vec<&'static str> = vec![
         "ucal", "udat", "udata", "uenum", "ustring", "utext", "uclean", "umsg",
-        "ucol", "uset",
+        "ucol", "uset", "ucnv", "ucsdet", "ubidi", "unorm2", "uregex",
+        "ufieldpositer", "unum", "udateintervalformat", "uldn", "ures", "unumberformatter",
     ];
 
     // C functions that will be made available to rust code.  Add more to this list if you want to
@@ -51,6 +52,21 @@ lazy_static! {
         "utext_.*",
         "umsg_.*",
         "ucol_.*",
+        "ucnv_.*",
+        "ucsdet_.*",
+        "UCNV_.*",
+        "ubidi_.*",
+        "unorm2_.*",
+        "uset_.*",
+        "uregex_.*",
+        "ufieldpositer_.*",
+        "unum_.*",
+        "udtitvfmt_.*",
+        "ufmtval_.*",
+        "ucfpos_.*",
+        "uldn_.*",
+        "ures_.*",
+        "unumf_.*",
     ];
 
     // C types that will be made available to rust code.  Add more to this list if you want to
@@ -71,6 +87,26 @@ lazy_static! {
         "UCollator",
         "USet",
         "UCol.*",
+        "UConverter.*",
+        "UCharsetDetector",
+        "UCharsetMatch",
+        "UBiDi.*",
+        "UNormaliz.*",
+        "URegularExpression",
+        "URegexpFlag",
+        "UFieldPosition.*",
+        "UNumberFormat.*",
+        "UDateIntervalFormat",
+        "UFormattedDateInterval",
+        "UFormattedValue",
+        "UConstrainedFieldPosition",
+        "UFieldCategory",
+        "ULocaleDisplayNames",
+        "UDialectHandling",
+        "UDisplayContext.*",
+        "UResourceBundle",
+        "UResType",
+        "UFormattedNumber",
     ];
 }
 
@@ -0,0 +1,437 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU bidirectional text support for rust
+//!
+//! This crate provides the [Unicode Bidirectional
+//! Algorithm](https://www.unicode.org/reports/tr9/), which determines the display order of text
+//! that mixes left-to-right scripts (such as Latin) with right-to-left scripts (such as Arabic or
+//! Hebrew), as implemented by the ICU library.  Specifically the functionality exposed through
+//! its C API, as available in the [header
+//! `ubidi.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/ubidi_8h.html).
+//!
+//! The main type is [UBiDi], which holds one paragraph of text along with its resolved embedding
+//! levels.  Once the paragraph is set, the text can be examined as a sequence of visual runs,
+//! each of which has a single direction, or written out in display order in its entirety.
+//!
+//! All indexes and lengths in this crate are counted in UTF-16 code units, as is usual in ICU.
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_icu_sys as sys;
+//! use rust_icu_ubidi as ubidi;
+//! let bidi = ubidi::UBiDi::try_from_paragraph("abc אבג", ubidi::DEFAULT_LTR).expect("bidi");
+//! assert_eq!(sys::UBiDiDirection::UBIDI_MIXED, bidi.direction());
+//!
+//! let runs = bidi.visual_runs().expect("runs");
+//! assert_eq!(
+//!     vec![
+//!         ubidi::VisualRun {
+//!             logical_start: 0,
+//!             length: 4,
+//!             direction: sys::UBiDiDirection::UBIDI_LTR
+//!         },
+//!         ubidi::VisualRun {
+//!             logical_start: 4,
+//!             length: 3,
+//!             direction: sys::UBiDiDirection::UBIDI_RTL
+//!         },
+//!     ],
+//!     runs
+//! );
+//!
+//! assert_eq!("abc גבא", bidi.write_reordered(ubidi::DO_MIRRORING).expect("reordered"));
+//! ```
+
+use {
+    rust_icu_common as common,
+    rust_icu_common::buffered_uchar_method_with_retry,
+    rust_icu_sys as sys,
+    rust_icu_sys::versioned_function,
+    rust_icu_sys::*,
+    rust_icu_ustring as ustring,
+    std::{convert::TryFrom, ptr},
+};
+
+/// An embedding level.  Even levels are left-to-right, odd levels are right-to-left.
+pub type Level = sys::UBiDiLevel;
+
+/// Paragraph level for left-to-right text.
+pub const LTR: Level = 0;
+
+/// Paragraph level for right-to-left text.
+pub const RTL: Level = 1;
+
+/// Paragraph level requesting that the level be determined from the text, falling back to
+/// left-to-right if the text has no strongly directional characters.  This is ICU's
+/// `UBIDI_DEFAULT_LTR`.
+pub const DEFAULT_LTR: Level = 0xfe;
+
+/// Same as [DEFAULT_LTR], but falls back to right-to-left.  This is ICU's `UBIDI_DEFAULT_RTL`.
+pub const DEFAULT_RTL: Level = 0xff;
+
+/// Option for [UBiDi::write_reordered]: keeps combining characters after their base character
+/// in the output.  This is ICU's `UBIDI_KEEP_BASE_COMBINING`.
+pub const KEEP_BASE_COMBINING: u16 = 1;
+
+/// Option for [UBiDi::write_reordered]: replaces characters with the "mirrored" property in
+/// right-to-left runs by their mirror-image counterparts, e.g. `(` with `)`.  This is ICU's
+/// `UBIDI_DO_MIRRORING`.
+pub const DO_MIRRORING: u16 = 2;
+
+/// Option for [UBiDi::write_reordered]: surrounds numeric runs with LRM characters.  This is
+/// ICU's `UBIDI_INSERT_LRM_FOR_NUMERIC`.
+pub const INSERT_LRM_FOR_NUMERIC: u16 = 4;
+
+/// Option for [UBiDi::write_reordered]: removes bidi control characters from the output.  This
+/// is ICU's `UBIDI_REMOVE_BIDI_CONTROLS`.
+pub const REMOVE_BIDI_CONTROLS: u16 = 8;
+
+/// Option for [UBiDi::write_reordered]: writes the output in reverse order.  This is ICU's
+/// `UBIDI_OUTPUT_REVERSE`.
+pub const OUTPUT_REVERSE: u16 = 16;
+
+/// A maximal sequence of text with the same direction, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisualRun {
+    /// The index of the first character of the run in the logical (input) text.
+    pub logical_start: usize,
+    /// The number of UTF-16 code units in the run.
+    pub length: usize,
+    /// The direction of the run, which is either `UBIDI_LTR` or `UBIDI_RTL`.
+    pub direction: sys::UBiDiDirection,
+}
+
+/// Holds a paragraph of text and its bidi analysis.
+///
+/// Implements `UBiDi` from ICU.
+#[derive(Debug)]
+pub struct UBiDi {
+    rep: ptr::NonNull<sys::UBiDi>,
+    // The paragraph text.  ICU keeps a pointer to the text instead of copying it, so the text
+    // must live as long as `rep` refers to it.
+    text: ustring::UChar,
+}
+
+impl Drop for UBiDi {
+    /// Implements `ubidi_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(ubidi_close)(self.rep.as_ptr()) };
+    }
+}
+
+impl UBiDi {
+    /// Creates a new, empty bidi object.  Use [UBiDi::set_paragraph] to give it some text.
+    ///
+    /// Implements `ubidi_open`
+    pub fn new() -> UBiDi {
+        let rep = unsafe { versioned_function!(ubidi_open)() };
+        UBiDi {
+            rep: ptr::NonNull::new(rep).expect("ubidi_open can fail only when out of memory"),
+            text: ustring::UChar::new_with_capacity(0),
+        }
+    }
+
+    /// Creates a new bidi object for the paragraph `text`.  See [UBiDi::set_paragraph] for the
+    /// meaning of `para_level`.
+    ///
+    /// Implements `ubidi_open` and `ubidi_setPara`
+    pub fn try_from_paragraph(text: &str, para_level: Level) -> Result<UBiDi, common::Error> {
+        let mut bidi = UBiDi::new();
+        bidi.set_paragraph(text, para_level)?;
+        Ok(bidi)
+    }
+
+    /// Sets the paragraph `text` and resolves its embedding levels.
+    ///
+    /// `para_level` is the base direction of the paragraph: one of [LTR] or [RTL] to set it
+    /// explicitly, or [DEFAULT_LTR] or [DEFAULT_RTL] to derive it from the first strongly
+    /// directional character of `text`.
+    ///
+    /// Implements `ubidi_setPara`
+    pub fn set_paragraph(&mut self, text: &str, para_level: Level) -> Result<(), common::Error> {
+        let text = ustring::UChar::try_from(text)?;
+        self.set_paragraph_uchar(text, para_level)
+    }
+
+    /// Same as [UBiDi::set_paragraph], but takes a [ustring::UChar].
+    ///
+    /// Implements `ubidi_setPara`
+    pub fn set_paragraph_uchar(
+        &mut self,
+        text: ustring::UChar,
+        para_level: Level,
+    ) -> Result<(), common::Error> {
+        let mut status = common::Error::OK_CODE;
+        // Requires that text outlives its use in self.rep, which is ensured by storing it in
+        // self below.  Moving the UChar does not move its contents.
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ubidi_setPara)(
+                self.rep.as_ptr(),
+                text.as_c_ptr(),
+                text.len() as i32,
+                para_level,
+                ptr::null_mut(),
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("ubidi_setPara", status)?;
+        self.text = text;
+        Ok(())
+    }
+
+    /// Returns the overall direction of the paragraph: `UBIDI_LTR` or `UBIDI_RTL` if all of
+    /// the text has the same direction, and `UBIDI_MIXED` otherwise.
+    ///
+    /// Implements `ubidi_getDirection`
+    pub fn direction(&self) -> sys::UBiDiDirection {
+        unsafe { versioned_function!(ubidi_getDirection)(self.rep.as_ptr()) }
+    }
+
+    /// Returns the resolved paragraph level.
+    ///
+    /// Implements `ubidi_getParaLevel`
+    pub fn para_level(&self) -> Level {
+        unsafe { versioned_function!(ubidi_getParaLevel)(self.rep.as_ptr()) }
+    }
+
+    /// Returns the length of the paragraph text, in UTF-16 code units.
+    ///
+    /// Implements `ubidi_getLength`
+    pub fn len(&self) -> usize {
+        unsafe { versioned_function!(ubidi_getLength)(self.rep.as_ptr()) as usize }
+    }
+
+    /// Returns true if the paragraph text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the resolved level of the UTF-16 code unit at `index`.  Returns the paragraph
+    /// level if `index` is out of range.
+    ///
+    /// Implements `ubidi_getLevelAt`
+    pub fn level_at(&self, index: usize) -> Level {
+        unsafe { versioned_function!(ubidi_getLevelAt)(self.rep.as_ptr(), index as i32) }
+    }
+
+    /// Returns the resolved levels of all UTF-16 code units of the paragraph, in logical order.
+    ///
+    /// Implements `ubidi_getLevels`
+    pub fn levels(&self) -> Result<Vec<Level>, common::Error> {
+        // ICU reports an empty paragraph as an error, so handle it separately.
+        let len = unsafe { versioned_function!(ubidi_getProcessedLength)(self.rep.as_ptr()) };
+        if len == 0 {
+            return Ok(vec![]);
+        }
+        let mut status = common::Error::OK_CODE;
+        let levels = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ubidi_getLevels)(self.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("ubidi_getLevels", status)?;
+        // Requires that levels points to an array of the processed length, owned by self.rep.
+        let levels = unsafe { std::slice::from_raw_parts(levels, len as usize) };
+        Ok(levels.to_vec())
+    }
+
+    /// Returns the number of visual runs in the paragraph.
+    ///
+    /// Implements `ubidi_countRuns`
+    pub fn count_runs(&self) -> Result<usize, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let count = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ubidi_countRuns)(self.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("ubidi_countRuns", status)?;
+        Ok(count as usize)
+    }
+
+    /// Returns the visual run at `index`, where runs are numbered in display order, from left
+    /// to right.  The index must be less than [UBiDi::count_runs].
+    ///
+    /// Implements `ubidi_getVisualRun`
+    pub fn visual_run(&self, index: usize) -> Result<VisualRun, common::Error> {
+        let count = self.count_runs()?;
+        if index >= count {
+            return Err(common::Error::Sys {
+                function: "ubidi_getVisualRun",
+                code: sys::UErrorCode::U_INDEX_OUTOFBOUNDS_ERROR,
+            });
+        }
+        let mut logical_start: i32 = 0;
+        let mut length: i32 = 0;
+        // Requires that the runs have been computed, which count_runs above ensures.
+        let direction = unsafe {
+            versioned_function!(ubidi_getVisualRun)(
+                self.rep.as_ptr(),
+                index as i32,
+                &mut logical_start,
+                &mut length,
+            )
+        };
+        Ok(VisualRun {
+            logical_start: logical_start as usize,
+            length: length as usize,
+            direction,
+        })
+    }
+
+    /// Returns all the visual runs of the paragraph, in display order.
+    ///
+    /// Implements `ubidi_countRuns` and `ubidi_getVisualRun`
+    pub fn visual_runs(&self) -> Result<Vec<VisualRun>, common::Error> {
+        let count = self.count_runs()?;
+        (0..count).map(|index| self.visual_run(index)).collect()
+    }
+
+    /// Returns the paragraph text in display order.
+    ///
+    /// `options` is a bitwise or of zero or more of [KEEP_BASE_COMBINING], [DO_MIRRORING],
+    /// [INSERT_LRM_FOR_NUMERIC], [REMOVE_BIDI_CONTROLS] and [OUTPUT_REVERSE].
+    ///
+    /// Implements `ubidi_writeReordered`
+    pub fn write_reordered_uchar(&self, options: u16) -> Result<ustring::UChar, common::Error> {
+        const CAPACITY: usize = 200;
+        buffered_uchar_method_with_retry!(
+            write_reordered_impl,
+            CAPACITY,
+            [rep: *mut sys::UBiDi,],
+            [options: u16,]
+        );
+        write_reordered_impl(
+            "ubidi_writeReordered",
+            versioned_function!(ubidi_writeReordered),
+            self.rep.as_ptr(),
+            options,
+        )
+    }
+
+    /// Same as [UBiDi::write_reordered_uchar], but returns a rust string.
+    ///
+    /// Implements `ubidi_writeReordered`
+    pub fn write_reordered(&self, options: u16) -> Result<String, common::Error> {
+        let result = self.write_reordered_uchar(options)?;
+        String::try_from(&result)
+    }
+}
+
+impl Default for UBiDi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the base direction of `text`, determined by its first strongly directional
+/// character.  Returns `UBIDI_NEUTRAL` if there is no such character.
+///
+/// Implements `ubidi_getBaseDirection`
+pub fn base_direction(text: &str) -> Result<sys::UBiDiDirection, common::Error> {
+    let text = ustring::UChar::try_from(text)?;
+    Ok(unsafe { versioned_function!(ubidi_getBaseDirection)(text.as_c_ptr(), text.len() as i32) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_directions() -> Result<(), common::Error> {
+        assert_eq!(sys::UBiDiDirection::UBIDI_LTR, base_direction("abc אבג")?);
+        assert_eq!(
+            sys::UBiDiDirection::UBIDI_RTL,
+            base_direction("123 אבג abc")?
+        );
+        assert_eq!(sys::UBiDiDirection::UBIDI_NEUTRAL, base_direction("123 !")?);
+        Ok(())
+    }
+
+    #[test]
+    fn default_paragraph_level() -> Result<(), common::Error> {
+        let bidi = UBiDi::try_from_paragraph("אבג abc", DEFAULT_LTR)?;
+        assert_eq!(RTL, bidi.para_level());
+        let bidi = UBiDi::try_from_paragraph("123", DEFAULT_RTL)?;
+        assert_eq!(RTL, bidi.para_level());
+        let bidi = UBiDi::try_from_paragraph("123", DEFAULT_LTR)?;
+        assert_eq!(LTR, bidi.para_level());
+        Ok(())
+    }
+
+    #[test]
+    fn levels() -> Result<(), common::Error> {
+        let bidi = UBiDi::try_from_paragraph("ab אב", LTR)?;
+        assert_eq!(5, bidi.len());
+        assert_eq!(vec![0, 0, 0, 1, 1], bidi.levels()?);
+        assert_eq!(1, bidi.level_at(4));
+        assert_eq!(sys::UBiDiDirection::UBIDI_MIXED, bidi.direction());
+        Ok(())
+    }
+
+    #[test]
+    fn visual_runs_rtl_paragraph() -> Result<(), common::Error> {
+        // In a right-to-left paragraph, the first run on the left is the last one logically.
+        let bidi = UBiDi::try_from_paragraph("אבג abc", RTL)?;
+        assert_eq!(2, bidi.count_runs()?);
+        assert_eq!(
+            vec![
+                VisualRun {
+                    logical_start: 4,
+                    length: 3,
+                    direction: sys::UBiDiDirection::UBIDI_LTR,
+                },
+                VisualRun {
+                    logical_start: 0,
+                    length: 4,
+                    direction: sys::UBiDiDirection::UBIDI_RTL,
+                },
+            ],
+            bidi.visual_runs()?
+        );
+        let result = bidi.visual_run(2);
+        assert!(result.is_err(), "was: {:?}", result);
+        Ok(())
+    }
+
+    #[test]
+    fn write_reordered() -> Result<(), common::Error> {
+        let mut bidi = UBiDi::new();
+        assert!(bidi.is_empty());
+        bidi.set_paragraph("אב(ג)", RTL)?;
+        assert_eq!("(ג)בא", bidi.write_reordered(DO_MIRRORING)?);
+        assert_eq!(")ג(בא", bidi.write_reordered(0)?);
+
+        let long = "abc אבג ".repeat(100);
+        bidi.set_paragraph(&long, LTR)?;
+        let reordered = bidi.write_reordered(0)?;
+        assert_eq!("abc גבא ".repeat(100), reordered);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_paragraph() -> Result<(), common::Error> {
+        let bidi = UBiDi::try_from_paragraph("", DEFAULT_LTR)?;
+        assert!(bidi.is_empty());
+        assert_eq!(Vec::<Level>::new(), bidi.levels()?);
+        assert_eq!(Vec::<VisualRun>::new(), bidi.visual_runs()?);
+        assert_eq!("", bidi.write_reordered(0)?);
+        Ok(())
+    }
+}
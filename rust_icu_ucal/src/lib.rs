@@ -71,7 +71,7 @@ impl UCalendar {
                 &mut status,
             ) as *mut sys::UCalendar
         };
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("ucal_open", status)?;
         Ok(UCalendar { rep: raw_ucal })
     }
 
@@ -100,7 +100,7 @@ impl UCalendar {
         unsafe {
             versioned_function!(ucal_setMillis)(self.rep, date_time, &mut status);
         };
-        common::Error::ok_or_warning(status)
+        common::Error::ok_or_warning("ucal_setMillis", status)
     }
 
     /// Gets the calendar's current date/time in milliseconds since the epoch.
@@ -109,7 +109,7 @@ impl UCalendar {
     pub fn get_millis(&self) -> Result<sys::UDate, common::Error> {
         let mut status = common::Error::OK_CODE;
         let millis = unsafe { versioned_function!(ucal_getMillis)(self.rep, &mut status) };
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("ucal_getMillis", status)?;
         Ok(millis)
     }
 
@@ -123,7 +123,7 @@ impl UCalendar {
         unsafe {
             versioned_function!(ucal_setDate)(self.rep, year, month, date, &mut status);
         }
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("ucal_setDate", status)?;
         Ok(())
     }
 
@@ -154,7 +154,7 @@ impl UCalendar {
                 &mut status,
             );
         }
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("ucal_setDateTime", status)?;
         Ok(())
     }
 
@@ -187,7 +187,7 @@ impl UCalendar {
         let mut status = common::Error::OK_CODE;
         let in_daylight_time: sys::UBool =
             unsafe { versioned_function!(ucal_inDaylightTime)(self.as_c_calendar(), &mut status) };
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("ucal_inDaylightTime", status)?;
         Ok(in_daylight_time != 0)
     }
 
@@ -198,9 +198,144 @@ impl UCalendar {
         let mut status: UErrorCode = common::Error::OK_CODE;
         let value =
             unsafe { versioned_function!(ucal_get)(self.as_c_calendar(), field, &mut status) };
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("ucal_get", status)?;
         Ok(value)
     }
+
+    /// Sets the value of a single field, such as the year or the hour.  The calendar's time is
+    /// recomputed on the next call that needs it.
+    ///
+    /// Note that `UCAL_MONTH` is 0-based.
+    ///
+    /// Implements `ucal_set`.
+    pub fn set(&mut self, field: UCalendarDateFields, value: i32) {
+        unsafe { versioned_function!(ucal_set)(self.rep, field, value) };
+    }
+
+    /// Adds `amount` to `field`, carrying over into larger fields as needed.  For example,
+    /// adding one month to January 31st yields the last day of February.
+    ///
+    /// Implements `ucal_add`.
+    pub fn add(&mut self, field: UCalendarDateFields, amount: i32) -> Result<(), common::Error> {
+        let mut status = common::Error::OK_CODE;
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucal_add)(self.rep, field, amount, &mut status);
+        };
+        common::Error::ok_or_warning("ucal_add", status)
+    }
+
+    /// Adds `amount` to `field` without changing larger fields.  For example, rolling December
+    /// 31st forward by one month yields January 31st of the same year.
+    ///
+    /// Implements `ucal_roll`.
+    pub fn roll(&mut self, field: UCalendarDateFields, amount: i32) -> Result<(), common::Error> {
+        let mut status = common::Error::OK_CODE;
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucal_roll)(self.rep, field, amount, &mut status);
+        };
+        common::Error::ok_or_warning("ucal_roll", status)
+    }
+
+    /// Returns the difference between the calendar's current date/time and `target`, in units
+    /// of `field`.  For example, with `UCAL_MONTH` this returns the number of whole months
+    /// between the two dates.
+    ///
+    /// As a side effect, the calendar is advanced by the returned amount, so that calling this
+    /// method repeatedly with decreasing field sizes yields the difference in each of the fields.
+    ///
+    /// Implements `ucal_getFieldDifference`.
+    pub fn get_field_difference(
+        &mut self,
+        target: sys::UDate,
+        field: UCalendarDateFields,
+    ) -> Result<i32, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let difference = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucal_getFieldDifference)(self.rep, target, field, &mut status)
+        };
+        common::Error::ok_or_warning("ucal_getFieldDifference", status)?;
+        Ok(difference)
+    }
+
+    /// Returns the first day of the week in this calendar's locale, e.g. `UCAL_SUNDAY` in the
+    /// US and `UCAL_MONDAY` in France.
+    ///
+    /// Wraps `ucal_getAttribute` for `UCAL_FIRST_DAY_OF_WEEK`.
+    pub fn get_first_day_of_week(&self) -> Result<UCalendarDaysOfWeek, common::Error> {
+        let day = unsafe {
+            versioned_function!(ucal_getAttribute)(
+                self.rep,
+                UCalendarAttribute::UCAL_FIRST_DAY_OF_WEEK,
+            )
+        };
+        day_of_week_from_i32(day)
+    }
+
+    /// Sets the first day of the week.
+    ///
+    /// Wraps `ucal_setAttribute` for `UCAL_FIRST_DAY_OF_WEEK`.
+    pub fn set_first_day_of_week(&mut self, day: UCalendarDaysOfWeek) {
+        unsafe {
+            versioned_function!(ucal_setAttribute)(
+                self.rep,
+                UCalendarAttribute::UCAL_FIRST_DAY_OF_WEEK,
+                day as i32,
+            )
+        };
+    }
+
+    /// Returns whether `day` is a weekday, a weekend day, or a day on which the weekend starts
+    /// or ends, in this calendar's locale.
+    ///
+    /// Implements `ucal_getDayOfWeekType`.
+    pub fn get_day_of_week_type(
+        &self,
+        day: UCalendarDaysOfWeek,
+    ) -> Result<UCalendarWeekdayType, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let day_type = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucal_getDayOfWeekType)(self.rep, day, &mut status)
+        };
+        common::Error::ok_or_warning("ucal_getDayOfWeekType", status)?;
+        Ok(day_type)
+    }
+
+    /// Returns true if `date` falls on a weekend in this calendar's locale.
+    ///
+    /// Implements `ucal_isWeekend`.
+    pub fn is_weekend(&self, date: sys::UDate) -> Result<bool, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let is_weekend = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucal_isWeekend)(self.rep, date, &mut status)
+        };
+        common::Error::ok_or_warning("ucal_isWeekend", status)?;
+        Ok(is_weekend != 0)
+    }
+}
+
+// Converts the value of the `UCAL_FIRST_DAY_OF_WEEK` attribute, which should be in the range 1
+// to 7, into the corresponding enum value.
+fn day_of_week_from_i32(day: i32) -> Result<UCalendarDaysOfWeek, common::Error> {
+    Ok(match day {
+        1 => UCalendarDaysOfWeek::UCAL_SUNDAY,
+        2 => UCalendarDaysOfWeek::UCAL_MONDAY,
+        3 => UCalendarDaysOfWeek::UCAL_TUESDAY,
+        4 => UCalendarDaysOfWeek::UCAL_WEDNESDAY,
+        5 => UCalendarDaysOfWeek::UCAL_THURSDAY,
+        6 => UCalendarDaysOfWeek::UCAL_FRIDAY,
+        7 => UCalendarDaysOfWeek::UCAL_SATURDAY,
+        _ => {
+            return Err(common::Error::Sys {
+                function: "ucal_getAttribute",
+                code: UErrorCode::U_INTERNAL_PROGRAM_ERROR,
+            })
+        }
+    })
 }
 
 /// Implements `ucal_setDefaultTimeZone`
@@ -213,7 +348,7 @@ pub fn set_default_time_zone(zone_id: &str) -> Result<(), common::Error> {
         assert!(common::Error::is_ok(status));
         versioned_function!(ucal_setDefaultTimeZone)(zone_id_uchar.as_c_ptr(), &mut status);
     };
-    common::Error::ok_or_warning(status)
+    common::Error::ok_or_warning("ucal_setDefaultTimeZone", status)
 }
 
 /// Implements `ucal_getDefaultTimeZone`
@@ -225,7 +360,7 @@ pub fn get_default_time_zone() -> Result<String, common::Error> {
         assert!(common::Error::is_ok(status));
         versioned_function!(ucal_getDefaultTimeZone)(0 as *mut sys::UChar, 0, &mut status)
     } as usize;
-    common::Error::ok_preflight(status)?;
+    common::Error::ok_preflight("ucal_getDefaultTimeZone", status)?;
 
     // Should this capacity include the terminating \u{0}?
     let mut status = common::Error::OK_CODE;
@@ -241,18 +376,42 @@ pub fn get_default_time_zone() -> Result<String, common::Error> {
             &mut status,
         )
     };
-    common::Error::ok_or_warning(status)?;
+    common::Error::ok_or_warning("ucal_getDefaultTimeZone", status)?;
     trace!("result: {:?}", uchar);
     String::try_from(&uchar)
 }
 
+/// Returns the canonical ID of the time zone `zone_id`, for example `America/Los_Angeles` for
+/// `US/Pacific`.
+///
+/// Implements `ucal_getCanonicalTimeZoneID`
+pub fn get_canonical_time_zone_id(zone_id: &str) -> Result<String, common::Error> {
+    const CAPACITY: usize = 100;
+    common::buffered_uchar_method_with_retry!(
+        get_canonical_time_zone_id_uchar,
+        CAPACITY,
+        [id: *const sys::UChar, len: i32,],
+        [is_system_id: *mut sys::UBool,]
+    );
+    let zone_id_uchar = ustring::UChar::try_from(zone_id)?;
+    let mut is_system_id: sys::UBool = 0;
+    let canonical = get_canonical_time_zone_id_uchar(
+        "ucal_getCanonicalTimeZoneID",
+        versioned_function!(ucal_getCanonicalTimeZoneID),
+        zone_id_uchar.as_c_ptr(),
+        zone_id_uchar.len() as i32,
+        &mut is_system_id,
+    )?;
+    String::try_from(&canonical)
+}
+
 /// Implements `ucal_getTZDataVersion`
 pub fn get_tz_data_version() -> Result<String, common::Error> {
     let mut status = common::Error::OK_CODE;
 
     let tz_data_version = unsafe {
         let raw_cstring = versioned_function!(ucal_getTZDataVersion)(&mut status);
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("ucal_getTZDataVersion", status)?;
         ffi::CStr::from_ptr(raw_cstring)
             .to_string_lossy()
             .into_owned()
@@ -494,4 +653,103 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_set_add_roll() -> Result<(), common::Error> {
+        let mut cal = UCalendar::new("UTC", "en-US", UCalendarType::UCAL_GREGORIAN)?;
+        cal.set_date_time(2020, UCalendarMonths::UCAL_JANUARY as i32, 31, 10, 0, 0)?;
+
+        cal.add(UCalendarDateFields::UCAL_MONTH, 1)?;
+        assert_eq!(cal.get(UCalendarDateFields::UCAL_MONTH)?, 1);
+        assert_eq!(cal.get(UCalendarDateFields::UCAL_DAY_OF_MONTH)?, 29);
+
+        cal.set(
+            UCalendarDateFields::UCAL_MONTH,
+            UCalendarMonths::UCAL_DECEMBER as i32,
+        );
+        cal.set(UCalendarDateFields::UCAL_DAY_OF_MONTH, 31);
+        cal.roll(UCalendarDateFields::UCAL_MONTH, 1)?;
+        assert_eq!(cal.get(UCalendarDateFields::UCAL_YEAR)?, 2020);
+        assert_eq!(cal.get(UCalendarDateFields::UCAL_MONTH)?, 0);
+        assert_eq!(cal.get(UCalendarDateFields::UCAL_DAY_OF_MONTH)?, 31);
+
+        cal.add(UCalendarDateFields::UCAL_HOUR_OF_DAY, 20)?;
+        assert_eq!(cal.get(UCalendarDateFields::UCAL_MONTH)?, 1);
+        assert_eq!(cal.get(UCalendarDateFields::UCAL_DAY_OF_MONTH)?, 1);
+        assert_eq!(cal.get(UCalendarDateFields::UCAL_HOUR_OF_DAY)?, 6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_field_difference() -> Result<(), common::Error> {
+        let date_format = iso_8601_format()?;
+        let start = date_format.parse("2020-01-15T00:00:00.000+00:00")?;
+        let end = date_format.parse("2021-03-20T06:00:00.000+00:00")?;
+
+        let mut cal = UCalendar::new("UTC", "en-US", UCalendarType::UCAL_GREGORIAN)?;
+        cal.set_millis(start)?;
+        assert_eq!(
+            cal.get_field_difference(end, UCalendarDateFields::UCAL_YEAR)?,
+            1
+        );
+        assert_eq!(
+            cal.get_field_difference(end, UCalendarDateFields::UCAL_MONTH)?,
+            2
+        );
+        assert_eq!(
+            cal.get_field_difference(end, UCalendarDateFields::UCAL_DATE)?,
+            5
+        );
+        assert_eq!(
+            cal.get_field_difference(end, UCalendarDateFields::UCAL_HOUR_OF_DAY)?,
+            6
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_week_data() -> Result<(), common::Error> {
+        let mut cal = UCalendar::new("UTC", "en-US", UCalendarType::UCAL_GREGORIAN)?;
+        assert_eq!(
+            cal.get_first_day_of_week()?,
+            UCalendarDaysOfWeek::UCAL_SUNDAY
+        );
+        cal.set_first_day_of_week(UCalendarDaysOfWeek::UCAL_WEDNESDAY);
+        assert_eq!(
+            cal.get_first_day_of_week()?,
+            UCalendarDaysOfWeek::UCAL_WEDNESDAY
+        );
+
+        let cal = UCalendar::new("UTC", "fr-FR", UCalendarType::UCAL_GREGORIAN)?;
+        assert_eq!(
+            cal.get_first_day_of_week()?,
+            UCalendarDaysOfWeek::UCAL_MONDAY
+        );
+        assert_eq!(
+            cal.get_day_of_week_type(UCalendarDaysOfWeek::UCAL_WEDNESDAY)?,
+            UCalendarWeekdayType::UCAL_WEEKDAY
+        );
+        assert_eq!(
+            cal.get_day_of_week_type(UCalendarDaysOfWeek::UCAL_SUNDAY)?,
+            UCalendarWeekdayType::UCAL_WEEKEND
+        );
+
+        let date_format = iso_8601_format()?;
+        // A Saturday, and a Monday.
+        assert!(cal.is_weekend(date_format.parse("2020-05-09T12:00:00.000+00:00")?)?);
+        assert!(!cal.is_weekend(date_format.parse("2020-05-11T12:00:00.000+00:00")?)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_canonical_time_zone_id() -> Result<(), common::Error> {
+        assert_eq!(
+            get_canonical_time_zone_id("US/Pacific")?,
+            "America/Los_Angeles"
+        );
+        assert_eq!(get_canonical_time_zone_id("Europe/Paris")?, "Europe/Paris");
+        assert!(get_canonical_time_zone_id("Not/A_Zone").is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,433 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU character set conversion support for rust
+//!
+//! This crate provides conversion between legacy character encodings (such as `Shift_JIS`,
+//! `ISO-8859-1` or `windows-1252`) and Unicode, as implemented by the ICU library.  Specifically
+//! the functionality exposed through its C API, as available in the [header
+//! `ucnv.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/ucnv_8h.html).
+//!
+//! The main type is [Converter].  A converter is stateful, and can be fed the input text in
+//! chunks of arbitrary size, for example as it arrives over the network.  A byte sequence that is
+//! split across two chunks is decoded correctly, as long as `flush` is only set on the last chunk.
+//!
+//! The behavior of the converter on malformed or unmappable input is configured through
+//! [Converter::set_to_unicode_action] and [Converter::set_from_unicode_action].
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_icu_ucnv as ucnv;
+//! let mut converter = ucnv::Converter::open("Shift_JIS").expect("converter");
+//! // "日本" in Shift_JIS, with the first character split across two chunks.
+//! let mut result = converter.to_string(&[0x93], false).expect("first chunk");
+//! result.push_str(&converter.to_string(&[0xfa, 0x96, 0x7b], true).expect("last chunk"));
+//! assert_eq!("日本", result);
+//! ```
+
+use {
+    rust_icu_common as common, rust_icu_sys as sys,
+    rust_icu_sys::versioned_function,
+    rust_icu_sys::*,
+    rust_icu_ustring as ustring,
+    std::{convert::TryFrom, ffi, os::raw, ptr},
+};
+
+// Extra room allocated in the output buffers on top of the size estimated from the input.
+// The buffers are grown as needed, so this only needs to cover the common case.
+const BUFFER_SLACK: usize = 16;
+
+/// The action a [Converter] takes when it encounters input that is malformed or can not be
+/// mapped to the target encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// Stops the conversion and reports an error.  This is ICU's `UCNV_*_CALLBACK_STOP`.
+    Stop,
+    /// Silently drops the offending input.  This is ICU's `UCNV_*_CALLBACK_SKIP`.
+    Skip,
+    /// Replaces the offending input with the substitution character.  This is the default
+    /// action, and is ICU's `UCNV_*_CALLBACK_SUBSTITUTE`.
+    Substitute,
+    /// Replaces the offending input with an escape sequence, such as `%UD800` or `%XFF`.  This
+    /// is ICU's `UCNV_*_CALLBACK_ESCAPE`.
+    Escape,
+}
+
+/// A stateful converter between a named character encoding and Unicode.
+///
+/// Implements `UConverter` from ICU.
+#[derive(Debug)]
+pub struct Converter {
+    rep: ptr::NonNull<sys::UConverter>,
+}
+
+impl Drop for Converter {
+    /// Implements `ucnv_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(ucnv_close)(self.rep.as_ptr()) };
+    }
+}
+
+impl Converter {
+    /// Opens a converter for the encoding named `name`, for example `UTF-8` or `Shift_JIS`.
+    ///
+    /// Any alias of the encoding known to ICU can be used, and the names are matched loosely
+    /// (e.g. `shiftjis` is the same as `Shift_JIS`).
+    ///
+    /// Implements `ucnv_open`
+    pub fn open(name: &str) -> Result<Converter, common::Error> {
+        let asciiz_name = ffi::CString::new(name)?;
+        let mut status = common::Error::OK_CODE;
+        // Requires that asciiz_name is a valid C string.
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucnv_open)(asciiz_name.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("ucnv_open", status)?;
+        Ok(Converter {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Returns the canonical internal ICU name of the encoding used by this converter.
+    ///
+    /// Implements `ucnv_getName`
+    pub fn name(&self) -> Result<String, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let raw_name = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucnv_getName)(self.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("ucnv_getName", status)?;
+        // The returned name is owned by ICU and lives as long as the converter.
+        let name = unsafe { ffi::CStr::from_ptr(raw_name) };
+        Ok(name.to_str().map_err(common::Error::wrapper)?.to_string())
+    }
+
+    /// Resets the converter to its initial state, discarding any partial input seen so far.
+    ///
+    /// A converter should be reset before reuse after an error is reported.
+    ///
+    /// Implements `ucnv_reset`
+    pub fn reset(&mut self) {
+        unsafe { versioned_function!(ucnv_reset)(self.rep.as_ptr()) };
+    }
+
+    /// Sets the action to take on malformed or unmappable input when converting to Unicode.
+    ///
+    /// Implements `ucnv_setToUCallBack`
+    pub fn set_to_unicode_action(&mut self, action: ErrorAction) -> Result<(), common::Error> {
+        let callback: sys::UConverterToUCallback = match action {
+            ErrorAction::Stop => Some(versioned_function!(UCNV_TO_U_CALLBACK_STOP)),
+            ErrorAction::Skip => Some(versioned_function!(UCNV_TO_U_CALLBACK_SKIP)),
+            ErrorAction::Substitute => Some(versioned_function!(UCNV_TO_U_CALLBACK_SUBSTITUTE)),
+            ErrorAction::Escape => Some(versioned_function!(UCNV_TO_U_CALLBACK_ESCAPE)),
+        };
+        let mut status = common::Error::OK_CODE;
+        let mut old_callback: sys::UConverterToUCallback = None;
+        let mut old_context: *const raw::c_void = ptr::null();
+        // The built-in callbacks accept a null context, which selects their default behavior.
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucnv_setToUCallBack)(
+                self.rep.as_ptr(),
+                callback,
+                ptr::null(),
+                &mut old_callback,
+                &mut old_context,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("ucnv_setToUCallBack", status)
+    }
+
+    /// Sets the action to take on input that can not be represented in the target encoding when
+    /// converting from Unicode.
+    ///
+    /// Implements `ucnv_setFromUCallBack`
+    pub fn set_from_unicode_action(&mut self, action: ErrorAction) -> Result<(), common::Error> {
+        let callback: sys::UConverterFromUCallback = match action {
+            ErrorAction::Stop => Some(versioned_function!(UCNV_FROM_U_CALLBACK_STOP)),
+            ErrorAction::Skip => Some(versioned_function!(UCNV_FROM_U_CALLBACK_SKIP)),
+            ErrorAction::Substitute => Some(versioned_function!(UCNV_FROM_U_CALLBACK_SUBSTITUTE)),
+            ErrorAction::Escape => Some(versioned_function!(UCNV_FROM_U_CALLBACK_ESCAPE)),
+        };
+        let mut status = common::Error::OK_CODE;
+        let mut old_callback: sys::UConverterFromUCallback = None;
+        let mut old_context: *const raw::c_void = ptr::null();
+        // The built-in callbacks accept a null context, which selects their default behavior.
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucnv_setFromUCallBack)(
+                self.rep.as_ptr(),
+                callback,
+                ptr::null(),
+                &mut old_callback,
+                &mut old_context,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("ucnv_setFromUCallBack", status)
+    }
+
+    /// Sets the string that replaces unmappable input when converting from Unicode with
+    /// [ErrorAction::Substitute].
+    ///
+    /// Implements `ucnv_setSubstString`
+    pub fn set_substitution(&mut self, substitution: &str) -> Result<(), common::Error> {
+        let substitution = ustring::UChar::try_from(substitution)?;
+        let mut status = common::Error::OK_CODE;
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucnv_setSubstString)(
+                self.rep.as_ptr(),
+                substitution.as_c_ptr(),
+                substitution.len() as i32,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("ucnv_setSubstString", status)
+    }
+
+    /// Converts the next `chunk` of encoded input to Unicode.
+    ///
+    /// Input that ends in the middle of a byte sequence is retained by the converter and completed
+    /// by the next chunk.  Set `flush` to `true` on the last chunk of the input, so that any
+    /// retained incomplete sequence is reported according to the configured [ErrorAction].
+    ///
+    /// Implements `ucnv_toUnicode`
+    pub fn to_uchar(&mut self, chunk: &[u8], flush: bool) -> Result<ustring::UChar, common::Error> {
+        assert!(chunk.len() <= i32::MAX as usize);
+        // For the vast majority of encodings, one byte of input yields at most one UTF-16 unit.
+        let mut result = ustring::UChar::new_with_capacity(chunk.len() + BUFFER_SLACK);
+        let mut written: usize = 0;
+        let mut source = chunk.as_ptr() as *const raw::c_char;
+        // Requires that source and source_limit delimit chunk.
+        let source_limit = unsafe { source.add(chunk.len()) };
+        loop {
+            let mut status = common::Error::OK_CODE;
+            let capacity = result.len();
+            // The target pointers are recomputed on each pass since the buffer may have been
+            // reallocated by the resize below.  ICU advances `source` and `target` past the
+            // consumed input and the produced output respectively.
+            unsafe {
+                assert!(common::Error::is_ok(status));
+                let start = result.as_mut_c_ptr();
+                let mut target = start.add(written);
+                versioned_function!(ucnv_toUnicode)(
+                    self.rep.as_ptr(),
+                    &mut target,
+                    start.add(capacity),
+                    &mut source,
+                    source_limit,
+                    ptr::null_mut(),
+                    flush as sys::UBool,
+                    &mut status,
+                );
+                written = target.offset_from(start) as usize;
+            };
+            if status == sys::UErrorCode::U_BUFFER_OVERFLOW_ERROR {
+                result.resize(2 * capacity);
+                continue;
+            }
+            common::Error::ok_or_warning("ucnv_toUnicode", status)?;
+            break;
+        }
+        result.resize(written);
+        Ok(result)
+    }
+
+    /// Converts the next `chunk` of encoded input to a rust string.
+    ///
+    /// See [Converter::to_uchar] for the meaning of `flush`.
+    ///
+    /// Implements `ucnv_toUnicode`
+    pub fn to_string(&mut self, chunk: &[u8], flush: bool) -> Result<String, common::Error> {
+        let result = self.to_uchar(chunk, flush)?;
+        String::try_from(&result)
+    }
+
+    /// Converts the next `chunk` of Unicode input to the encoding of this converter.
+    ///
+    /// A surrogate pair that is split across two chunks is retained by the converter and
+    /// completed by the next chunk.  Set `flush` to `true` on the last chunk of the input.
+    ///
+    /// Implements `ucnv_fromUnicode`
+    pub fn from_uchar(
+        &mut self,
+        chunk: &ustring::UChar,
+        flush: bool,
+    ) -> Result<Vec<u8>, common::Error> {
+        assert!(chunk.len() <= i32::MAX as usize);
+        let mut result: Vec<u8> = vec![0; 2 * chunk.len() + BUFFER_SLACK];
+        let mut written: usize = 0;
+        let mut source = chunk.as_c_ptr();
+        // Requires that source and source_limit delimit chunk.
+        let source_limit = unsafe { source.add(chunk.len()) };
+        loop {
+            let mut status = common::Error::OK_CODE;
+            let capacity = result.len();
+            // See to_uchar for the notes on the pointer arithmetic.
+            unsafe {
+                assert!(common::Error::is_ok(status));
+                let start = result.as_mut_ptr() as *mut raw::c_char;
+                let mut target = start.add(written);
+                versioned_function!(ucnv_fromUnicode)(
+                    self.rep.as_ptr(),
+                    &mut target,
+                    start.add(capacity),
+                    &mut source,
+                    source_limit,
+                    ptr::null_mut(),
+                    flush as sys::UBool,
+                    &mut status,
+                );
+                written = target.offset_from(start) as usize;
+            };
+            if status == sys::UErrorCode::U_BUFFER_OVERFLOW_ERROR {
+                result.resize(2 * capacity, 0);
+                continue;
+            }
+            common::Error::ok_or_warning("ucnv_fromUnicode", status)?;
+            break;
+        }
+        result.truncate(written);
+        Ok(result)
+    }
+
+    /// Converts the next `chunk` of a rust string to the encoding of this converter.
+    ///
+    /// See [Converter::from_uchar] for the meaning of `flush`.
+    ///
+    /// Implements `ucnv_fromUnicode`
+    pub fn from_str(&mut self, chunk: &str, flush: bool) -> Result<Vec<u8>, common::Error> {
+        let chunk = ustring::UChar::try_from(chunk)?;
+        self.from_uchar(&chunk, flush)
+    }
+}
+
+/// Returns the canonical names of all the encodings that ICU can convert.
+///
+/// Implements `ucnv_countAvailable` and `ucnv_getAvailableName`
+pub fn available_converters() -> Result<Vec<String>, common::Error> {
+    let count = unsafe { versioned_function!(ucnv_countAvailable)() };
+    let mut result = Vec::with_capacity(count as usize);
+    for n in 0..count {
+        // The returned name is owned by ICU and is valid for indexes below count.
+        let name = unsafe { ffi::CStr::from_ptr(versioned_function!(ucnv_getAvailableName)(n)) };
+        result.push(name.to_str().map_err(common::Error::wrapper)?.to_string());
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name() -> Result<(), common::Error> {
+        let converter = Converter::open("utf8")?;
+        assert_eq!("UTF-8", converter.name()?);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_encoding() {
+        let result = Converter::open("no-such-encoding");
+        assert!(result.is_err(), "was: {:?}", result);
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), common::Error> {
+        let mut converter = Converter::open("windows-1252")?;
+        let encoded = converter.from_str("Grüße, €5", true)?;
+        assert_eq!(b"Gr\xfc\xdfe, \x805".to_vec(), encoded);
+        assert_eq!("Grüße, €5", converter.to_string(&encoded, true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn streaming_split_sequence() -> Result<(), common::Error> {
+        let mut converter = Converter::open("UTF-8")?;
+        let input = "❤ Hello ❤".as_bytes();
+        let mut result = String::new();
+        // Feed byte by byte, so that each multi-byte sequence is split.
+        for (i, byte) in input.iter().enumerate() {
+            result.push_str(&converter.to_string(&[*byte], i == input.len() - 1)?);
+        }
+        assert_eq!("❤ Hello ❤", result);
+        Ok(())
+    }
+
+    #[test]
+    fn large_input_grows_buffer() -> Result<(), common::Error> {
+        let mut converter = Converter::open("UTF-8")?;
+        let input = "🦀".repeat(1000);
+        let encoded = converter.from_str(&input, true)?;
+        assert_eq!(input.as_bytes(), &encoded[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn error_actions() -> Result<(), common::Error> {
+        let mut converter = Converter::open("UTF-8")?;
+        assert_eq!("a\u{fffd}b", converter.to_string(b"a\xffb", true)?);
+
+        converter.set_to_unicode_action(ErrorAction::Skip)?;
+        assert_eq!("ab", converter.to_string(b"a\xffb", true)?);
+
+        converter.set_to_unicode_action(ErrorAction::Escape)?;
+        assert_eq!("a%XFFb", converter.to_string(b"a\xffb", true)?);
+
+        converter.set_to_unicode_action(ErrorAction::Stop)?;
+        let result = converter.to_string(b"a\xffb", true);
+        assert!(result.is_err(), "was: {:?}", result);
+        converter.reset();
+        assert_eq!("ab", converter.to_string(b"ab", true)?);
+
+        // An incomplete sequence at the end of the input is an error only when flushing.
+        assert_eq!("a", converter.to_string(b"a\xe2\x9d", false)?);
+        let result = converter.to_string(b"", true);
+        assert!(result.is_err(), "was: {:?}", result);
+        Ok(())
+    }
+
+    #[test]
+    fn from_unicode_substitution() -> Result<(), common::Error> {
+        let mut converter = Converter::open("US-ASCII")?;
+        converter.set_substitution("?")?;
+        assert_eq!(b"a?b".to_vec(), converter.from_str("a❤b", true)?);
+
+        converter.set_from_unicode_action(ErrorAction::Stop)?;
+        let result = converter.from_str("a❤b", true);
+        assert!(result.is_err(), "was: {:?}", result);
+        Ok(())
+    }
+
+    #[test]
+    fn available() -> Result<(), common::Error> {
+        let converters = available_converters()?;
+        assert!(
+            converters.contains(&"UTF-8".to_string()),
+            "was: {:?}",
+            converters
+        );
+        Ok(())
+    }
+}
@@ -72,9 +72,17 @@ use {
     std::{cmp::Ordering, convert::TryFrom, ffi, ptr},
 };
 
+/// Compares strings according to the conventions of a locale.
+///
+/// A collator may be used from any thread, but not from several threads at once, so it is `Send`
+/// but not `Sync`.  Use [UCollator::try_clone] to give each thread its own copy.
+///
+/// Implements `UCollator` from ICU.
 #[derive(Debug)]
 pub struct UCollator {
     rep: ptr::NonNull<sys::UCollator>,
+    // The warning reported by ICU when this collator was opened, if any.
+    warning: Option<sys::UErrorCode>,
 }
 
 impl Drop for UCollator {
@@ -86,6 +94,17 @@ impl Drop for UCollator {
     }
 }
 
+unsafe impl Send for UCollator {}
+
+impl Clone for UCollator {
+    /// Makes an independent copy of this collator.
+    ///
+    /// Panics if ICU can not allocate the copy.  Use [UCollator::try_clone] to handle that case.
+    fn clone(&self) -> Self {
+        self.try_clone().expect("ucol_safeClone")
+    }
+}
+
 impl TryFrom<&str> for UCollator {
     type Error = common::Error;
     /// Makes a new collator from the supplied locale, e.g. `en-US`, or
@@ -106,14 +125,46 @@ impl TryFrom<&str> for UCollator {
             assert!(common::Error::is_ok(status));
             versioned_function!(ucol_open)(locale_cstr.as_ptr(), &mut status) as *mut sys::UCollator
         };
-        common::Error::ok_or_warning(status)?;
+        let warning = common::Error::ok_with_warning("ucol_open", status)?;
         Ok(UCollator {
             rep: ptr::NonNull::new(rep).unwrap(),
+            warning,
         })
     }
 }
 
 impl UCollator {
+    /// Makes an independent copy of this collator.  The copy shares the immutable collation
+    /// data with the original, so this is much cheaper than opening a new collator.
+    ///
+    /// Implements `ucol_safeClone`
+    pub fn try_clone(&self) -> Result<UCollator, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        // Passing a null stack buffer makes ICU allocate the clone on the heap, so that it can be
+        // released with `ucol_close` like any other collator.
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucol_safeClone)(
+                self.rep.as_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("ucol_safeClone", status)?;
+        Ok(UCollator {
+            rep: ptr::NonNull::new(rep).unwrap(),
+            warning: self.warning,
+        })
+    }
+
+    /// Returns the warning that ICU reported when this collator was opened, if any.  For
+    /// example, `U_USING_DEFAULT_WARNING` if there are no collation rules for the locale or any
+    /// of its parents other than the root locale.
+    pub fn warning(&self) -> Option<sys::UErrorCode> {
+        self.warning
+    }
+
     /// Compares strings `first` and `second` according to the collation rules in this collator.
     ///
     /// Returns [Ordering::Less] if `first` compares as less than `second`, and for other return
@@ -164,7 +215,7 @@ impl UCollator {
                 &mut status,
             )
         };
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("ucol_strcollUTF8", status)?;
         Ok(UCollator::to_rust_ordering(result))
     }
 
@@ -212,4 +263,33 @@ mod tests {
         assert_eq!(alphabet, mixed_up);
         Ok(())
     }
+
+    #[test]
+    fn open_warning() -> Result<(), common::Error> {
+        // Swedish has its own collation rules, German uses the ones of the root locale.
+        assert_eq!(None, crate::UCollator::try_from("sv")?.warning());
+        let collator = crate::UCollator::try_from("de")?;
+        assert_eq!(
+            Some(sys::UErrorCode::U_USING_DEFAULT_WARNING),
+            collator.warning()
+        );
+        assert_eq!(collator.warning(), collator.try_clone()?.warning());
+        Ok(())
+    }
+
+    #[test]
+    fn clone_in_another_thread() -> Result<(), common::Error> {
+        let collator = crate::UCollator::try_from("sr-Latn")?;
+        let copy = collator.try_clone()?;
+        let sorted = std::thread::spawn(move || {
+            let mut mixed_up = vec!["đ", "dž", "d"];
+            mixed_up.sort_by(|a, b| copy.strcoll_utf8(a, b).expect("strcoll_utf8"));
+            mixed_up
+        })
+        .join()
+        .expect("thread finished");
+        assert_eq!(vec!["d", "dž", "đ"], sorted);
+        assert_eq!(Ordering::Less, collator.strcoll_utf8("dž", "đ")?);
+        Ok(())
+    }
 }
@@ -0,0 +1,306 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU character set detection support for rust
+//!
+//! This crate provides heuristic detection of the character encoding of a piece of text, as
+//! implemented by the ICU library.  Specifically the functionality exposed through its C API, as
+//! available in the [header
+//! `ucsdet.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/ucsdet_8h.html).
+//!
+//! The main type is [CharsetDetector].  Detection yields one or more [CharsetMatch]es, each
+//! carrying the name of the encoding and a confidence value.  Detection is statistical and can
+//! be wrong, especially on short inputs.  The name of a match can be passed directly to
+//! `rust_icu_ucnv::Converter::open` to decode the text.
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+//!
+//! ## Examples
+//!
+//! Detecting the encoding of a byte stream, then decoding it.
+//!
+//! ```
+//! use rust_icu_ucnv as ucnv;
+//! use rust_icu_ucsdet as ucsdet;
+//! let text = "Ein Text, der über Fußgänger und Grüße in einer älteren Kodierung spricht.";
+//! let mut converter = ucnv::Converter::open("ISO-8859-1").expect("converter");
+//! let bytes = converter.from_str(text, true).expect("encoded");
+//!
+//! let mut detector = ucsdet::CharsetDetector::new().expect("detector");
+//! let best = detector.detect(&bytes).expect("detect").expect("some match");
+//! assert_eq!("ISO-8859-1", best.name);
+//! assert_eq!("de", best.language);
+//!
+//! let mut converter = ucnv::Converter::open(&best.name).expect("converter");
+//! assert_eq!(text, converter.to_string(&bytes, true).expect("decoded"));
+//! ```
+
+use {
+    rust_icu_common as common, rust_icu_sys as sys,
+    rust_icu_sys::versioned_function,
+    rust_icu_sys::*,
+    rust_icu_uenum as uenum,
+    std::{ffi, os::raw, ptr},
+};
+
+/// A single candidate encoding found by a [CharsetDetector].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharsetMatch {
+    /// The name of the encoding, e.g. `UTF-8` or `Shift_JIS`.
+    pub name: String,
+    /// The confidence in this match, from 0 (none) to 100 (complete).
+    pub confidence: i32,
+    /// The language of the text, as an ISO language code.  Empty if the detector could not
+    /// determine the language, which is the case for most encodings.
+    pub language: String,
+}
+
+impl CharsetMatch {
+    /// Copies the contents of an ICU-owned match.
+    ///
+    /// Implements `ucsdet_getName`, `ucsdet_getConfidence` and `ucsdet_getLanguage`
+    fn from_raw(raw: *const sys::UCharsetMatch) -> Result<CharsetMatch, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        // Requires that raw points to a valid match.  The returned strings are owned by ICU and
+        // remain valid until the next detection.
+        let (name, confidence, language) = unsafe {
+            assert!(common::Error::is_ok(status));
+            let name = versioned_function!(ucsdet_getName)(raw, &mut status);
+            common::Error::ok_or_warning("ucsdet_getName", status)?;
+            let confidence = versioned_function!(ucsdet_getConfidence)(raw, &mut status);
+            common::Error::ok_or_warning("ucsdet_getConfidence", status)?;
+            let language = versioned_function!(ucsdet_getLanguage)(raw, &mut status);
+            common::Error::ok_or_warning("ucsdet_getLanguage", status)?;
+            (to_string(name)?, confidence, to_string(language)?)
+        };
+        Ok(CharsetMatch {
+            name,
+            confidence,
+            language,
+        })
+    }
+}
+
+// Converts a possibly null C string owned by ICU into a rust string.
+unsafe fn to_string(raw: *const raw::c_char) -> Result<String, common::Error> {
+    if raw.is_null() {
+        return Ok("".to_string());
+    }
+    let cstr = ffi::CStr::from_ptr(raw);
+    Ok(cstr.to_str().map_err(common::Error::wrapper)?.to_string())
+}
+
+/// Detects the character encoding of text.
+///
+/// Implements `UCharsetDetector` from ICU.
+#[derive(Debug)]
+pub struct CharsetDetector {
+    rep: ptr::NonNull<sys::UCharsetDetector>,
+}
+
+impl Drop for CharsetDetector {
+    /// Implements `ucsdet_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(ucsdet_close)(self.rep.as_ptr()) };
+    }
+}
+
+impl CharsetDetector {
+    /// Creates a new charset detector.
+    ///
+    /// Implements `ucsdet_open`
+    pub fn new() -> Result<CharsetDetector, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucsdet_open)(&mut status)
+        };
+        common::Error::ok_or_warning("ucsdet_open", status)?;
+        Ok(CharsetDetector {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Sets the encoding that the text is claimed to be in, for example from an HTTP header.
+    ///
+    /// The detector is meant to use this as a hint only.  Note that current ICU versions accept
+    /// the declared encoding, but do not use it for detection, so setting it has no effect.
+    /// Returns an error if `encoding` contains a NUL byte.
+    ///
+    /// Implements `ucsdet_setDeclaredEncoding`
+    pub fn set_declared_encoding(&mut self, encoding: &str) -> Result<(), common::Error> {
+        let mut status = common::Error::OK_CODE;
+        // ICU copies one byte past the given length, so the name must be NUL terminated.  ICU
+        // does not keep the encoding name after this call returns.
+        let asciiz_encoding = ffi::CString::new(encoding)?;
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucsdet_setDeclaredEncoding)(
+                self.rep.as_ptr(),
+                asciiz_encoding.as_ptr(),
+                -1,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("ucsdet_setDeclaredEncoding", status)
+    }
+
+    /// Enables or disables filtering of markup (e.g. HTML tags) from the text before detection.
+    /// Returns the previous setting.
+    ///
+    /// Implements `ucsdet_enableInputFilter`
+    pub fn enable_input_filter(&mut self, enabled: bool) -> bool {
+        let previous = unsafe {
+            versioned_function!(ucsdet_enableInputFilter)(self.rep.as_ptr(), enabled as sys::UBool)
+        };
+        previous != 0
+    }
+
+    /// Returns the names of all the encodings that the detector can recognize.
+    ///
+    /// Implements `ucsdet_getAllDetectableCharsets`
+    pub fn detectable_charsets(&self) -> Result<uenum::Enumeration, common::Error> {
+        // Requires that self.rep is a valid detector, which it is by construction.
+        unsafe { uenum::ucsdet_get_all_detectable_charsets(self.rep.as_ptr()) }
+    }
+
+    /// Returns the best matching encoding for `text`, or `None` if no encoding matches at all.
+    ///
+    /// Implements `ucsdet_setText` and `ucsdet_detect`
+    pub fn detect(&mut self, text: &[u8]) -> Result<Option<CharsetMatch>, common::Error> {
+        self.set_text(text)?;
+        let mut status = common::Error::OK_CODE;
+        // The match is owned by the detector, and is only valid until the next detection.  It is
+        // copied out before this method returns.
+        let raw = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucsdet_detect)(self.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("ucsdet_detect", status)?;
+        if raw.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(CharsetMatch::from_raw(raw)?))
+    }
+
+    /// Returns all encodings that match `text`, in order of decreasing confidence.
+    ///
+    /// Implements `ucsdet_setText` and `ucsdet_detectAll`
+    pub fn detect_all(&mut self, text: &[u8]) -> Result<Vec<CharsetMatch>, common::Error> {
+        self.set_text(text)?;
+        let mut status = common::Error::OK_CODE;
+        let mut count: i32 = 0;
+        // As in detect, the matches are owned by the detector and are copied out.
+        let raw = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucsdet_detectAll)(self.rep.as_ptr(), &mut count, &mut status)
+        };
+        common::Error::ok_or_warning("ucsdet_detectAll", status)?;
+        if raw.is_null() {
+            return Ok(vec![]);
+        }
+        // Requires that raw points to an array of count matches.
+        let matches = unsafe { std::slice::from_raw_parts(raw, count as usize) };
+        matches.iter().map(|m| CharsetMatch::from_raw(*m)).collect()
+    }
+
+    // Sets the text to examine.  The detector does not copy the text, so this must be followed by
+    // detection before `text` goes out of scope.
+    //
+    // Implements `ucsdet_setText`
+    fn set_text(&mut self, text: &[u8]) -> Result<(), common::Error> {
+        assert!(text.len() <= i32::MAX as usize);
+        let mut status = common::Error::OK_CODE;
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucsdet_setText)(
+                self.rep.as_ptr(),
+                text.as_ptr() as *const raw::c_char,
+                text.len() as i32,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("ucsdet_setText", status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rust_icu_ucnv as ucnv};
+
+    #[test]
+    fn detect_utf8() -> Result<(), common::Error> {
+        let mut detector = CharsetDetector::new()?;
+        let text = "Дебютный альбом группы вышел в тысяча девятьсот девяносто первом году.";
+        let best = detector.detect(text.as_bytes())?.expect("a match");
+        assert_eq!("UTF-8", best.name);
+        assert!(best.confidence > 50, "was: {:?}", best);
+        Ok(())
+    }
+
+    #[test]
+    fn detect_all_ranked() -> Result<(), common::Error> {
+        let mut converter = ucnv::Converter::open("Shift_JIS")?;
+        let text = "日本語の文章をシフトJISで符号化して、文字コードを判定します。";
+        let bytes = converter.from_str(text, true)?;
+
+        let mut detector = CharsetDetector::new()?;
+        let matches = detector.detect_all(&bytes)?;
+        assert!(!matches.is_empty());
+        assert_eq!("Shift_JIS", matches[0].name, "was: {:?}", matches);
+        assert_eq!("ja", matches[0].language, "was: {:?}", matches);
+        let confidences: Vec<i32> = matches.iter().map(|m| m.confidence).collect();
+        let mut sorted = confidences.clone();
+        sorted.sort_by(|a, b| b.cmp(a));
+        assert_eq!(sorted, confidences);
+
+        let mut converter = ucnv::Converter::open(&matches[0].name)?;
+        assert_eq!(text, converter.to_string(&bytes, true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn declared_encoding_and_filter() -> Result<(), common::Error> {
+        let mut detector = CharsetDetector::new()?;
+        detector.set_declared_encoding("UTF-8")?;
+        // Not followed by a NUL byte in memory.
+        let names = String::from("UTF-8Shift_JIS");
+        detector.set_declared_encoding(&names[..5])?;
+        assert!(detector.set_declared_encoding("UTF\08").is_err());
+        assert!(!detector.enable_input_filter(true));
+        assert!(detector.enable_input_filter(false));
+        Ok(())
+    }
+
+    #[test]
+    fn detectable() -> Result<(), common::Error> {
+        let detector = CharsetDetector::new()?;
+        let charsets = detector
+            .detectable_charsets()?
+            .collect::<Result<Vec<String>, common::Error>>()?;
+        assert!(
+            charsets.contains(&"UTF-8".to_string()),
+            "was: {:?}",
+            charsets
+        );
+        assert!(
+            charsets.contains(&"Shift_JIS".to_string()),
+            "was: {:?}",
+            charsets
+        );
+        Ok(())
+    }
+}
@@ -23,11 +23,14 @@
 
 use {
     rust_icu_common as common, rust_icu_sys as sys, rust_icu_sys::versioned_function,
-    rust_icu_sys::*, rust_icu_ucal as ucal, rust_icu_uloc as uloc, rust_icu_ustring as ustring,
-    std::convert::TryFrom,
+    rust_icu_sys::*, rust_icu_ucal as ucal, rust_icu_ufieldpositer as ufieldpositer,
+    rust_icu_uloc as uloc, rust_icu_ustring as ustring, std::convert::TryFrom,
 };
 
 /// Implements `UDateFormat`
+///
+/// A date format may be used from any thread, but not from several threads at once, so it is
+/// `Send` but not `Sync`.  Use [UDateFormat::try_clone] to give each thread its own copy.
 pub struct UDateFormat {
     // Internal C representation of UDateFormat.  It is owned by this type and
     // must be dropped by calling `udat_close`.
@@ -43,6 +46,18 @@ impl Drop for UDateFormat {
     }
 }
 
+unsafe impl Send for UDateFormat {}
+
+impl Clone for UDateFormat {
+    /// Makes an independent copy of this date format.
+    ///
+    /// Panics if ICU can not allocate the copy.  Use [UDateFormat::try_clone] to handle that
+    /// case.
+    fn clone(&self) -> Self {
+        self.try_clone().expect("udat_clone")
+    }
+}
+
 /// Parsed contains output of the call to `UDateFormat::parse_from_position`.
 pub struct Parsed {
     /// The point in time parsed out of the date-time string.
@@ -139,10 +154,23 @@ impl UDateFormat {
                 &mut status,
             )
         };
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("udat_open", status)?;
         Ok(UDateFormat { rep: date_format })
     }
 
+    /// Makes an independent copy of this date format.
+    ///
+    /// Implements `udat_clone`
+    pub fn try_clone(&self) -> Result<UDateFormat, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(udat_clone)(self.rep, &mut status)
+        };
+        common::Error::ok_or_warning("udat_clone", status)?;
+        Ok(UDateFormat { rep })
+    }
+
     /// Implements `udat_setCalendar`
     pub fn set_calendar(&mut self, calendar: &ucal::UCalendar) {
         unsafe {
@@ -188,7 +216,7 @@ impl UDateFormat {
                 &mut status,
             )
         };
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("udat_parse", status)?;
         Ok(Parsed {
             date,
             end_position: end_position as usize,
@@ -224,7 +252,7 @@ impl UDateFormat {
                 &mut status,
             )
         } as usize;
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("udat_format", status)?;
         result.resize(total_size as usize);
         if total_size > CAP {
             // Requires that result is a buffer that has length and capacity of
@@ -241,10 +269,37 @@ impl UDateFormat {
                     &mut status,
                 );
             };
-            common::Error::ok_or_warning(status)?;
+            common::Error::ok_or_warning("udat_format", status)?;
         }
         String::try_from(&result)
     }
+
+    /// Formats a date using this formatter, and reports the parts that the formatted text is made
+    /// of, such as the day of the week or the month.  This is the equivalent of `formatToParts`
+    /// from ECMA 402.
+    ///
+    /// Implements `udat_formatForFields`
+    pub fn format_to_parts(
+        &self,
+        date_to_format: sys::UDate,
+    ) -> Result<ufieldpositer::FormattedParts<sys::UDateFormatField>, common::Error> {
+        const CAPACITY: usize = 1024;
+        common::buffered_uchar_method_with_retry!(
+            format_for_fields,
+            CAPACITY,
+            [format: *const sys::UDateFormat, date_to_format: sys::UDate,],
+            [positions: *mut sys::UFieldPositionIterator,]
+        );
+        let mut positions = ufieldpositer::UFieldPositionIterator::try_new()?;
+        let result = format_for_fields(
+            "udat_formatForFields",
+            versioned_function!(udat_formatForFields),
+            self.rep,
+            date_to_format,
+            positions.as_mut_ptr(),
+        )?;
+        ufieldpositer::FormattedParts::try_new(&result, positions, ufieldpositer::date_format_field)
+    }
 }
 
 #[cfg(test)]
@@ -436,4 +491,47 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn format_to_parts() -> Result<(), common::Error> {
+        use sys::UDateFormatField::*;
+        let loc = uloc::ULoc::try_from("de-DE")?;
+        let tz_id = ustring::UChar::try_from("Europe/Berlin")?;
+        let pattern = ustring::UChar::try_from("EEEE, d. MMMM yyyy 'um' HH:mm")?;
+        let format = UDateFormat::new_with_pattern(&loc, &tz_id, &pattern)?;
+
+        // 2020-03-02T17:30:00Z
+        let parts = format.format_to_parts(1583170200000.0)?;
+        assert_eq!("Montag, 2. März 2020 um 18:30", parts.text());
+        let parts: Vec<(sys::UDateFormatField, &str)> =
+            parts.iter().map(|(field, text)| (*field, text)).collect();
+        assert_eq!(
+            vec![
+                (UDAT_DAY_OF_WEEK_FIELD, "Montag"),
+                (UDAT_DATE_FIELD, "2"),
+                (UDAT_MONTH_FIELD, "März"),
+                (UDAT_YEAR_FIELD, "2020"),
+                (UDAT_HOUR_OF_DAY0_FIELD, "18"),
+                (UDAT_MINUTE_FIELD, "30"),
+            ],
+            parts
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn clone_in_another_thread() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("de-DE")?;
+        let tz_id = ustring::UChar::try_from("Europe/Berlin")?;
+        let pattern = ustring::UChar::try_from("d. MMMM yyyy, HH:mm")?;
+        let format = UDateFormat::new_with_pattern(&loc, &tz_id, &pattern)?;
+
+        let copy = format.try_clone()?;
+        let formatted = std::thread::spawn(move || copy.format(1583170200000.0))
+            .join()
+            .expect("thread finished")?;
+        assert_eq!("2. März 2020, 18:30", formatted);
+        assert_eq!(formatted, format.format(1583170200000.0)?);
+        Ok(())
+    }
 }
@@ -14,7 +14,7 @@
 
 use {
     rust_icu_common as common, rust_icu_sys as sys, rust_icu_sys::versioned_function,
-    rust_icu_sys::*, std::convert::TryFrom, std::os::raw,
+    rust_icu_sys::*, std::convert::TryFrom, std::ffi, std::os::raw,
 };
 
 /// Implements `UDataMemory`.
@@ -55,7 +55,83 @@ impl TryFrom<Vec<u8>> for crate::UDataMemory {
                 &mut status,
             );
         };
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("udata_setCommonData", status)?;
         Ok(UDataMemory { buf })
     }
 }
+
+/// Makes the ICU data in `data` available to ICU, for example an application specific build of
+/// the ICU data that was embedded into the binary with `include_bytes!`.  The data is used in
+/// addition to the data that ICU already has.
+///
+/// `data` must be the contents of a common data (`.dat`) file, aligned to 16 bytes.  It is
+/// borrowed for the lifetime of the program, because ICU keeps referring to it.  Best call this
+/// early at startup, before any other ICU function, since ICU may have loaded some of its data
+/// already.
+///
+/// # Safety
+///
+/// ICU is not told the length of `data`, and trusts the sizes and offsets stored within it.  The
+/// caller must make sure that `data` is aligned to 16 bytes, and that it is either a complete and
+/// well-formed ICU common data file, or does not start with the ICU data header at all.  Other
+/// data may make ICU read past the end of `data`.
+///
+/// Implements `udata_setCommonData`.
+pub unsafe fn set_common_data(data: &'static [u8]) -> Result<(), common::Error> {
+    let mut status = common::Error::OK_CODE;
+    // Requires that data is valid for the lifetime of the program, which it is since it is
+    // static.  If the data header is invalid, an error status will be set.
+    assert!(common::Error::is_ok(status));
+    versioned_function!(udata_setCommonData)(data.as_ptr() as *const raw::c_void, &mut status);
+    common::Error::ok_or_warning("udata_setCommonData", status)
+}
+
+/// Makes the ICU data in `data` available under the package name `package_name`.  Application
+/// resource bundles packaged this way can then be opened by passing `package_name` to, for
+/// example, `rust_icu_ures::UResourceBundle::try_new`.
+///
+/// `data` must be the contents of a common data (`.dat`) file, aligned to 16 bytes.  It is
+/// borrowed for the lifetime of the program, because ICU keeps referring to it.
+///
+/// # Safety
+///
+/// ICU is not told the length of `data`, and trusts the sizes and offsets stored within it.  The
+/// caller must make sure that `data` is aligned to 16 bytes, and that it is either a complete and
+/// well-formed ICU common data file, or does not start with the ICU data header at all.  Other
+/// data may make ICU read past the end of `data`.
+///
+/// Implements `udata_setAppData`.
+pub unsafe fn set_app_data(package_name: &str, data: &'static [u8]) -> Result<(), common::Error> {
+    let mut status = common::Error::OK_CODE;
+    let asciiz_package_name = ffi::CString::new(package_name)?;
+    // Requires that data is valid for the lifetime of the program, which it is since it is
+    // static.  If the data header is invalid, an error status will be set.
+    assert!(common::Error::is_ok(status));
+    versioned_function!(udata_setAppData)(
+        asciiz_package_name.as_ptr(),
+        data.as_ptr() as *const raw::c_void,
+        &mut status,
+    );
+    common::Error::ok_or_warning("udata_setAppData", status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ICU data must be suitably aligned.
+    #[repr(C, align(16))]
+    struct Aligned<T>(T);
+
+    // Not a valid ICU data file, since the header is missing.
+    static INVALID_DATA: Aligned<[u8; 32]> = Aligned([0; 32]);
+
+    #[test]
+    fn invalid_data_is_rejected() {
+        // The data does not start with the ICU data header.
+        unsafe {
+            assert!(set_common_data(&INVALID_DATA.0).is_err());
+            assert!(set_app_data("invalid", &INVALID_DATA.0).is_err());
+        }
+    }
+}
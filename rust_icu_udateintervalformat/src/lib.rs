@@ -0,0 +1,425 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU date interval formatting support for rust
+//!
+//! This crate formats ranges of time, such as "Jan 3 – 5, 2024" or "10:00 – 11:30 AM", as
+//! implemented by the ICU library.  Specifically the functionality exposed through its C API, as
+//! available in the [header
+//! `udateintervalformat.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/udateintervalformat_8h.html).
+//!
+//! The formatter is created from a skeleton, which lists the fields to show, such as `yMMMd`
+//! for the year, abbreviated month and day.  Fields that are the same at both ends of the range
+//! are shown only once.
+//!
+//! [UDateIntervalFormat] also implements the `ecma402_traits::datetimeformat::DateTimeFormat`
+//! trait.
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_icu_udateintervalformat as udtitvfmt;
+//! use rust_icu_uloc as uloc;
+//! use rust_icu_ustring as ustring;
+//! use std::convert::TryFrom;
+//!
+//! let loc = uloc::ULoc::try_from("en-US").expect("locale");
+//! let skeleton = ustring::UChar::try_from("yMMMd").expect("skeleton");
+//! let tz_id = ustring::UChar::try_from("UTC").expect("time zone");
+//! let fmt = udtitvfmt::UDateIntervalFormat::try_new(&loc, &skeleton, &tz_id).expect("formatter");
+//!
+//! // 2024-01-03T00:00:00Z to 2024-01-05T00:00:00Z
+//! let range = fmt.format(1704240000000.0, 1704412800000.0).expect("formatted");
+//! assert_eq!("Jan 3\u{2009}–\u{2009}5, 2024", range);
+//! ```
+
+use {
+    ecma402_traits::datetimeformat, rust_icu_common as common, rust_icu_sys as sys,
+    rust_icu_sys::versioned_function, rust_icu_sys::*, rust_icu_ufieldpositer as ufieldpositer,
+    rust_icu_uloc as uloc, rust_icu_ustring as ustring, std::convert::TryFrom, std::fmt, std::ptr,
+};
+
+/// The initial size of the buffers for formatted intervals.
+const CAPACITY: usize = 200;
+
+/// The end of a formatted interval that a part of the text belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Span {
+    /// The text formats the start of the interval.
+    Start,
+    /// The text formats the end of the interval.
+    End,
+}
+
+/// A field of a formatted interval, as reported by
+/// [format_to_parts](UDateIntervalFormat::format_to_parts).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// A date or time field, such as the month.
+    Date(sys::UDateFormatField),
+    /// The text that formats one end of the interval.  Text that is in neither span is shared
+    /// by both ends.
+    Span(Span),
+}
+
+/// Formats intervals of time.
+///
+/// Implements `UDateIntervalFormat` from ICU.
+#[derive(Debug)]
+pub struct UDateIntervalFormat {
+    rep: ptr::NonNull<sys::UDateIntervalFormat>,
+}
+
+impl Drop for UDateIntervalFormat {
+    /// Implements `udtitvfmt_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(udtitvfmt_close)(self.rep.as_ptr()) };
+    }
+}
+
+impl UDateIntervalFormat {
+    /// Creates a new interval formatter for the locale `loc`, showing the fields from `skeleton`
+    /// in the time zone `tz_id`.
+    ///
+    /// Implements `udtitvfmt_open`
+    pub fn try_new(
+        loc: &uloc::ULoc,
+        skeleton: &ustring::UChar,
+        tz_id: &ustring::UChar,
+    ) -> Result<UDateIntervalFormat, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let asciiz_locale = loc.as_c_str();
+        // Requires that all pointers be valid, which is guaranteed by the types of the arguments.
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(udtitvfmt_open)(
+                asciiz_locale.as_ptr(),
+                skeleton.as_c_ptr(),
+                skeleton.len() as i32,
+                tz_id.as_c_ptr(),
+                tz_id.len() as i32,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("udtitvfmt_open", status)?;
+        Ok(UDateIntervalFormat {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Formats the interval from `from` to `to`.  If the two dates are the same in all the
+    /// fields of the skeleton, a single date is formatted.
+    ///
+    /// Implements `udtitvfmt_format`
+    pub fn format(&self, from: sys::UDate, to: sys::UDate) -> Result<String, common::Error> {
+        common::buffered_uchar_method_with_retry!(
+            format_interval,
+            CAPACITY,
+            [format: *const sys::UDateIntervalFormat, from: sys::UDate, to: sys::UDate,],
+            [position: *mut sys::UFieldPosition,]
+        );
+        let result = format_interval(
+            "udtitvfmt_format",
+            versioned_function!(udtitvfmt_format),
+            self.rep.as_ptr(),
+            from,
+            to,
+            ptr::null_mut(),
+        )?;
+        String::try_from(&result)
+    }
+
+    /// Formats the interval from `from` to `to`, and reports the parts that the formatted text
+    /// is made of: the date fields, and the spans of text for each end of the interval.  This is
+    /// the equivalent of `formatRangeToParts` from ECMA 402.
+    ///
+    /// Implements `udtitvfmt_formatToResult`
+    #[cfg(features = "icu_version_67_plus")]
+    pub fn format_to_parts(
+        &self,
+        from: sys::UDate,
+        to: sys::UDate,
+    ) -> Result<ufieldpositer::FormattedParts<Field>, common::Error> {
+        let result = FormattedDateInterval::try_new()?;
+        let mut status = common::Error::OK_CODE;
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(udtitvfmt_formatToResult)(
+                self.rep.as_ptr(),
+                from,
+                to,
+                result.rep.as_ptr(),
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("udtitvfmt_formatToResult", status)?;
+        result.parts()
+    }
+}
+
+impl datetimeformat::DateTimeFormat for UDateIntervalFormat {
+    fn format<W>(&self, date: f64, writer: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let result = UDateIntervalFormat::format(self, date, date).map_err(|_| fmt::Error)?;
+        writer.write_str(&result)
+    }
+
+    fn format_range<W>(&self, start: f64, end: f64, writer: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let result = UDateIntervalFormat::format(self, start, end).map_err(|_| fmt::Error)?;
+        writer.write_str(&result)
+    }
+}
+
+/// The result of formatting an interval, with the field information retained.
+///
+/// Implements `UFormattedDateInterval` from ICU.
+#[cfg(features = "icu_version_67_plus")]
+struct FormattedDateInterval {
+    rep: ptr::NonNull<sys::UFormattedDateInterval>,
+}
+
+#[cfg(features = "icu_version_67_plus")]
+impl Drop for FormattedDateInterval {
+    /// Implements `udtitvfmt_closeResult`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(udtitvfmt_closeResult)(self.rep.as_ptr()) };
+    }
+}
+
+#[cfg(features = "icu_version_67_plus")]
+impl FormattedDateInterval {
+    /// Implements `udtitvfmt_openResult`
+    fn try_new() -> Result<FormattedDateInterval, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(udtitvfmt_openResult)(&mut status)
+        };
+        common::Error::ok_or_warning("udtitvfmt_openResult", status)?;
+        Ok(FormattedDateInterval {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Copies out the formatted text, together with the fields in it.
+    ///
+    /// Implements `udtitvfmt_resultAsValue`, `ufmtval_getString` and `ufmtval_nextPosition`
+    fn parts(&self) -> Result<ufieldpositer::FormattedParts<Field>, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        // The value is owned by self, and lives as long as it does.
+        let value = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(udtitvfmt_resultAsValue)(self.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("udtitvfmt_resultAsValue", status)?;
+
+        let mut len: i32 = 0;
+        let raw = unsafe { versioned_function!(ufmtval_getString)(value, &mut len, &mut status) };
+        common::Error::ok_or_warning("ufmtval_getString", status)?;
+        let mut text = ustring::UChar::new_with_capacity(len as usize);
+        // Requires that raw points to len valid characters, which ICU guarantees.
+        unsafe { ptr::copy_nonoverlapping(raw, text.as_mut_c_ptr(), len as usize) };
+
+        let position = ConstrainedFieldPosition::try_new()?;
+        let mut fields = vec![];
+        loop {
+            let has_next = unsafe {
+                versioned_function!(ufmtval_nextPosition)(value, position.rep.as_ptr(), &mut status)
+            };
+            common::Error::ok_or_warning("ufmtval_nextPosition", status)?;
+            if has_next == 0 {
+                break;
+            }
+            if let Some(field) = position.field()? {
+                fields.push((field, position.range()?));
+            }
+        }
+        ufieldpositer::FormattedParts::try_from_utf16_ranges(&text, fields)
+    }
+}
+
+/// The cursor used to iterate over the fields of a formatted value.
+///
+/// Implements `UConstrainedFieldPosition` from ICU.
+#[cfg(features = "icu_version_67_plus")]
+struct ConstrainedFieldPosition {
+    rep: ptr::NonNull<sys::UConstrainedFieldPosition>,
+}
+
+#[cfg(features = "icu_version_67_plus")]
+impl Drop for ConstrainedFieldPosition {
+    /// Implements `ucfpos_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(ucfpos_close)(self.rep.as_ptr()) };
+    }
+}
+
+#[cfg(features = "icu_version_67_plus")]
+impl ConstrainedFieldPosition {
+    /// Implements `ucfpos_open`
+    fn try_new() -> Result<ConstrainedFieldPosition, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucfpos_open)(&mut status)
+        };
+        common::Error::ok_or_warning("ucfpos_open", status)?;
+        Ok(ConstrainedFieldPosition {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Returns the field at the current position, or `None` if it is not a field of a date
+    /// interval.
+    ///
+    /// Implements `ucfpos_getCategory` and `ucfpos_getField`
+    fn field(&self) -> Result<Option<Field>, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let (category, field) = unsafe {
+            assert!(common::Error::is_ok(status));
+            let category = versioned_function!(ucfpos_getCategory)(self.rep.as_ptr(), &mut status);
+            let field = versioned_function!(ucfpos_getField)(self.rep.as_ptr(), &mut status);
+            (category, field)
+        };
+        common::Error::ok_or_warning("ucfpos_getField", status)?;
+        if category == sys::UFieldCategory::UFIELD_CATEGORY_DATE as i32 {
+            return Ok(ufieldpositer::date_format_field(field).map(Field::Date));
+        }
+        if category == sys::UFieldCategory::UFIELD_CATEGORY_DATE_INTERVAL_SPAN as i32 {
+            return Ok(match field {
+                0 => Some(Field::Span(Span::Start)),
+                1 => Some(Field::Span(Span::End)),
+                _ => None,
+            });
+        }
+        Ok(None)
+    }
+
+    /// Returns the range of the field at the current position, in UTF-16 code units.
+    ///
+    /// Implements `ucfpos_getIndexes`
+    fn range(&self) -> Result<std::ops::Range<usize>, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let mut start: i32 = 0;
+        let mut limit: i32 = 0;
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ucfpos_getIndexes)(
+                self.rep.as_ptr(),
+                &mut start,
+                &mut limit,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("ucfpos_getIndexes", status)?;
+        Ok(start as usize..limit as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, ecma402_traits::datetimeformat::DateTimeFormat};
+
+    // 2024-01-03T10:00:00Z
+    const JAN_3_10AM: sys::UDate = 1704276000000.0;
+    const HOUR: sys::UDate = 3600000.0;
+    const DAY: sys::UDate = 24.0 * HOUR;
+
+    fn formatter(locale: &str, skeleton: &str) -> Result<UDateIntervalFormat, common::Error> {
+        let loc = uloc::ULoc::try_from(locale)?;
+        let skeleton = ustring::UChar::try_from(skeleton)?;
+        let tz_id = ustring::UChar::try_from("UTC")?;
+        UDateIntervalFormat::try_new(&loc, &skeleton, &tz_id)
+    }
+
+    #[test]
+    fn format_ranges() -> Result<(), common::Error> {
+        let fmt = formatter("en-US", "yMMMd")?;
+        assert_eq!(
+            "Jan 3\u{2009}–\u{2009}5, 2024",
+            fmt.format(JAN_3_10AM, JAN_3_10AM + 2.0 * DAY)?
+        );
+        assert_eq!(
+            "Jan 3\u{2009}–\u{2009}Feb 2, 2024",
+            fmt.format(JAN_3_10AM, JAN_3_10AM + 30.0 * DAY)?
+        );
+        assert_eq!("Jan 3, 2024", fmt.format(JAN_3_10AM, JAN_3_10AM + HOUR)?);
+
+        let fmt = formatter("en-US", "hm")?;
+        assert_eq!(
+            "10:00\u{2009}–\u{2009}11:30\u{202f}AM",
+            fmt.format(JAN_3_10AM, JAN_3_10AM + 1.5 * HOUR)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ecma402_format_range() -> Result<(), common::Error> {
+        let fmt = formatter("de-DE", "yMMMMd")?;
+        let mut range = String::new();
+        fmt.format_range(JAN_3_10AM, JAN_3_10AM + 2.0 * DAY, &mut range)
+            .expect("formatted range");
+        assert_eq!("3.–5. Januar 2024", range);
+
+        let mut date = String::new();
+        DateTimeFormat::format(&fmt, JAN_3_10AM, &mut date).expect("formatted date");
+        assert_eq!("3. Januar 2024", date);
+        Ok(())
+    }
+
+    #[cfg(features = "icu_version_67_plus")]
+    #[test]
+    fn format_to_parts() -> Result<(), common::Error> {
+        use sys::UDateFormatField::*;
+        let fmt = formatter("en-US", "yMMMd")?;
+        let parts = fmt.format_to_parts(JAN_3_10AM, JAN_3_10AM + 2.0 * DAY)?;
+        assert_eq!("Jan 3\u{2009}–\u{2009}5, 2024", parts.text());
+        // Orders the parts by their position in the text.  A span comes before the date fields
+        // that it contains, even if they cover the same text.
+        let mut sorted: Vec<&ufieldpositer::Part<Field>> = parts.parts().iter().collect();
+        sorted.sort_by_key(|part| {
+            (
+                part.range.start,
+                std::cmp::Reverse(part.range.end),
+                matches!(part.field, Field::Date(_)),
+            )
+        });
+        let sorted: Vec<(Field, &str)> = sorted
+            .into_iter()
+            .map(|part| (part.field, parts.get(part)))
+            .collect();
+        // The month is shared by both ends of the interval, and so is not within either span.
+        assert_eq!(
+            vec![
+                (Field::Date(UDAT_MONTH_FIELD), "Jan"),
+                (Field::Span(Span::Start), "3"),
+                (Field::Date(UDAT_DATE_FIELD), "3"),
+                (Field::Span(Span::End), "5"),
+                (Field::Date(UDAT_DATE_FIELD), "5"),
+                (Field::Date(UDAT_YEAR_FIELD), "2024"),
+            ],
+            sorted
+        );
+        Ok(())
+    }
+}
@@ -70,7 +70,7 @@ impl TryFrom<&[&str]> for Enumeration {
                 &mut status,
             )
         };
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("uenum_openCharStringsEnumeration", status)?;
         // rep should not be null without an error set, but:
         // https://unicode-org.atlassian.net/browse/ICU-20918
         assert!(!rep.is_null());
@@ -106,7 +106,7 @@ impl Iterator for Enumeration {
             // No more elements to iterate over.
             return None;
         }
-        let result = common::Error::ok_or_warning(status);
+        let result = common::Error::ok_or_warning("uenum_next", status);
         match result {
             Ok(()) => {
                 assert!(!raw.is_null());
@@ -134,7 +134,7 @@ pub fn ucal_open_country_time_zones(country: &str) -> Result<Enumeration, common
         assert!(common::Error::is_ok(status));
         versioned_function!(ucal_openCountryTimeZones)(asciiz_country.as_ptr(), &mut status)
     };
-    common::Error::ok_or_warning(status)?;
+    common::Error::ok_or_warning("ucal_openCountryTimeZones", status)?;
     Ok(Enumeration {
         raw: None,
         rep: raw_enum,
@@ -176,7 +176,7 @@ pub fn ucal_open_time_zone_id_enumeration(
             &mut status,
         )
     };
-    common::Error::ok_or_warning(status)?;
+    common::Error::ok_or_warning("ucal_openTimeZoneIDEnumeration", status)?;
     Ok(Enumeration {
         raw: None,
         rep: raw_enum,
@@ -195,7 +195,7 @@ pub fn open_time_zones() -> Result<Enumeration, common::Error> {
         assert!(common::Error::is_ok(status));
         versioned_function!(ucal_openTimeZones)(&mut status)
     };
-    common::Error::ok_or_warning(status)?;
+    common::Error::ok_or_warning("ucal_openTimeZones", status)?;
     Ok(Enumeration {
         raw: None,
         rep: raw_enum,
@@ -213,7 +213,7 @@ pub fn uloc_open_keywords(locale: &str) -> Result<Enumeration, common::Error> {
         assert!(common::Error::is_ok(status));
         versioned_function!(uloc_openKeywords)(asciiz_locale.as_ptr(), &mut status)
     };
-    common::Error::ok_or_warning(status)?;
+    common::Error::ok_or_warning("uloc_openKeywords", status)?;
     // "No error but null" means that there are no keywords
     if raw_enum.is_null() {
         Ok(Enumeration::empty())
@@ -225,6 +225,27 @@ pub fn uloc_open_keywords(locale: &str) -> Result<Enumeration, common::Error> {
     }
 }
 
+#[doc(hidden)]
+/// Implements `ucsdet_getAllDetectableCharsets`.
+///
+/// # Safety
+///
+/// `detector` must be a valid pointer to an open charset detector.
+// This should be in the `ucsdet` crate, but this is not possible because of the raw enum
+// initialization. Tested in `ucsdet`.
+pub unsafe fn ucsdet_get_all_detectable_charsets(
+    detector: *const sys::UCharsetDetector,
+) -> Result<Enumeration, common::Error> {
+    let mut status = common::Error::OK_CODE;
+    assert!(common::Error::is_ok(status));
+    let raw_enum = versioned_function!(ucsdet_getAllDetectableCharsets)(detector, &mut status);
+    common::Error::ok_or_warning("ucsdet_getAllDetectableCharsets", status)?;
+    Ok(Enumeration {
+        raw: None,
+        rep: raw_enum,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, std::convert::TryFrom};
@@ -0,0 +1,354 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU field position iteration support for rust
+//!
+//! This crate wraps the field position iterator from the [header
+//! `ufieldpositer.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/ufieldpositer_8h.html).
+//!
+//! Formatters such as `rust_icu_udat::UDateFormat` and `rust_icu_unum::UNumberFormat` fill a
+//! [UFieldPositionIterator] with the locations of the fields (the month, the currency symbol
+//! and so on) in the text that they produce.  The formatters use it to produce
+//! [FormattedParts], which is how most users will encounter this crate.
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+
+use {
+    rust_icu_common as common, rust_icu_sys as sys, rust_icu_sys::versioned_function,
+    rust_icu_sys::*, rust_icu_ustring as ustring, std::convert::TryFrom, std::ops, std::ptr,
+};
+
+/// The position of a single field in formatted text.
+///
+/// The positions are in UTF-16 code units, as reported by ICU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldPosition {
+    /// The field number.  Its meaning depends on the formatter, for example it is a
+    /// `sys::UDateFormatField` for date formatting.
+    pub field: i32,
+    /// The index of the first code unit of the field.
+    pub begin: usize,
+    /// The index one past the last code unit of the field.
+    pub end: usize,
+}
+
+/// Iterates over the positions of the fields in formatted text.
+///
+/// Implements `UFieldPositionIterator` from ICU.
+#[derive(Debug)]
+pub struct UFieldPositionIterator {
+    rep: ptr::NonNull<sys::UFieldPositionIterator>,
+}
+
+impl Drop for UFieldPositionIterator {
+    /// Implements `ufieldpositer_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(ufieldpositer_close)(self.rep.as_ptr()) };
+    }
+}
+
+impl UFieldPositionIterator {
+    /// Creates a new, empty, iterator.  It can be passed to a formatter to be filled in.
+    ///
+    /// Implements `ufieldpositer_open`
+    pub fn try_new() -> Result<UFieldPositionIterator, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ufieldpositer_open)(&mut status)
+        };
+        common::Error::ok_or_warning("ufieldpositer_open", status)?;
+        Ok(UFieldPositionIterator {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Returns the underlying representation, for use with the `*ForFields` functions of the
+    /// low-level C API.
+    pub fn as_mut_ptr(&mut self) -> *mut sys::UFieldPositionIterator {
+        self.rep.as_ptr()
+    }
+}
+
+impl Iterator for UFieldPositionIterator {
+    type Item = FieldPosition;
+
+    /// Implements `ufieldpositer_next`
+    fn next(&mut self) -> Option<FieldPosition> {
+        let mut begin: i32 = 0;
+        let mut end: i32 = 0;
+        let field = unsafe {
+            versioned_function!(ufieldpositer_next)(self.rep.as_ptr(), &mut begin, &mut end)
+        };
+        if field < 0 {
+            return None;
+        }
+        Some(FieldPosition {
+            field,
+            begin: begin as usize,
+            end: end as usize,
+        })
+    }
+}
+
+/// The date format fields, in the order of their field numbers.
+const DATE_FORMAT_FIELDS: &[sys::UDateFormatField] = &[
+    sys::UDateFormatField::UDAT_ERA_FIELD,
+    sys::UDateFormatField::UDAT_YEAR_FIELD,
+    sys::UDateFormatField::UDAT_MONTH_FIELD,
+    sys::UDateFormatField::UDAT_DATE_FIELD,
+    sys::UDateFormatField::UDAT_HOUR_OF_DAY1_FIELD,
+    sys::UDateFormatField::UDAT_HOUR_OF_DAY0_FIELD,
+    sys::UDateFormatField::UDAT_MINUTE_FIELD,
+    sys::UDateFormatField::UDAT_SECOND_FIELD,
+    sys::UDateFormatField::UDAT_FRACTIONAL_SECOND_FIELD,
+    sys::UDateFormatField::UDAT_DAY_OF_WEEK_FIELD,
+    sys::UDateFormatField::UDAT_DAY_OF_YEAR_FIELD,
+    sys::UDateFormatField::UDAT_DAY_OF_WEEK_IN_MONTH_FIELD,
+    sys::UDateFormatField::UDAT_WEEK_OF_YEAR_FIELD,
+    sys::UDateFormatField::UDAT_WEEK_OF_MONTH_FIELD,
+    sys::UDateFormatField::UDAT_AM_PM_FIELD,
+    sys::UDateFormatField::UDAT_HOUR1_FIELD,
+    sys::UDateFormatField::UDAT_HOUR0_FIELD,
+    sys::UDateFormatField::UDAT_TIMEZONE_FIELD,
+    sys::UDateFormatField::UDAT_YEAR_WOY_FIELD,
+    sys::UDateFormatField::UDAT_DOW_LOCAL_FIELD,
+    sys::UDateFormatField::UDAT_EXTENDED_YEAR_FIELD,
+    sys::UDateFormatField::UDAT_JULIAN_DAY_FIELD,
+    sys::UDateFormatField::UDAT_MILLISECONDS_IN_DAY_FIELD,
+    sys::UDateFormatField::UDAT_TIMEZONE_RFC_FIELD,
+    sys::UDateFormatField::UDAT_TIMEZONE_GENERIC_FIELD,
+    sys::UDateFormatField::UDAT_STANDALONE_DAY_FIELD,
+    sys::UDateFormatField::UDAT_STANDALONE_MONTH_FIELD,
+    sys::UDateFormatField::UDAT_QUARTER_FIELD,
+    sys::UDateFormatField::UDAT_STANDALONE_QUARTER_FIELD,
+    sys::UDateFormatField::UDAT_TIMEZONE_SPECIAL_FIELD,
+    sys::UDateFormatField::UDAT_YEAR_NAME_FIELD,
+    sys::UDateFormatField::UDAT_TIMEZONE_LOCALIZED_GMT_OFFSET_FIELD,
+    sys::UDateFormatField::UDAT_TIMEZONE_ISO_FIELD,
+    sys::UDateFormatField::UDAT_TIMEZONE_ISO_LOCAL_FIELD,
+    sys::UDateFormatField::UDAT_RELATED_YEAR_FIELD,
+    sys::UDateFormatField::UDAT_AM_PM_MIDNIGHT_NOON_FIELD,
+    sys::UDateFormatField::UDAT_FLEXIBLE_DAY_PERIOD_FIELD,
+    sys::UDateFormatField::UDAT_TIME_SEPARATOR_FIELD,
+];
+
+/// The number format fields, in the order of their field numbers.
+const NUMBER_FORMAT_FIELDS: &[sys::UNumberFormatFields] = &[
+    sys::UNumberFormatFields::UNUM_INTEGER_FIELD,
+    sys::UNumberFormatFields::UNUM_FRACTION_FIELD,
+    sys::UNumberFormatFields::UNUM_DECIMAL_SEPARATOR_FIELD,
+    sys::UNumberFormatFields::UNUM_EXPONENT_SYMBOL_FIELD,
+    sys::UNumberFormatFields::UNUM_EXPONENT_SIGN_FIELD,
+    sys::UNumberFormatFields::UNUM_EXPONENT_FIELD,
+    sys::UNumberFormatFields::UNUM_GROUPING_SEPARATOR_FIELD,
+    sys::UNumberFormatFields::UNUM_CURRENCY_FIELD,
+    sys::UNumberFormatFields::UNUM_PERCENT_FIELD,
+    sys::UNumberFormatFields::UNUM_PERMILL_FIELD,
+    sys::UNumberFormatFields::UNUM_SIGN_FIELD,
+    #[cfg(features = "icu_version_64_plus")]
+    sys::UNumberFormatFields::UNUM_MEASURE_UNIT_FIELD,
+    #[cfg(features = "icu_version_64_plus")]
+    sys::UNumberFormatFields::UNUM_COMPACT_FIELD,
+];
+
+/// Converts a field number reported by a date formatter into a date format field.
+///
+/// Returns `None` if `field` is not a date format field known to this crate.
+pub fn date_format_field(field: i32) -> Option<sys::UDateFormatField> {
+    DATE_FORMAT_FIELDS
+        .iter()
+        .copied()
+        .find(|known| *known as i32 == field)
+}
+
+/// Converts a field number reported by a number formatter into a number format field.
+///
+/// Returns `None` if `field` is not a number format field known to this crate.  This includes
+/// the fields added to ICU after version 67.
+pub fn number_format_field(field: i32) -> Option<sys::UNumberFormatFields> {
+    NUMBER_FORMAT_FIELDS
+        .iter()
+        .copied()
+        .find(|known| *known as i32 == field)
+}
+
+/// A single field of formatted text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part<F> {
+    /// What the field represents, for example `UDAT_MONTH_FIELD`.
+    pub field: F,
+    /// The byte range of the field within [FormattedParts::text].
+    pub range: ops::Range<usize>,
+}
+
+/// Formatted text, together with the parts that it is made of.
+///
+/// Only the fields reported by the formatter are included in the parts.  Literal text, such as
+/// the punctuation between the fields of a date, is not.  Fields may nest: for example the
+/// grouping separators of a number are within its integer part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormattedParts<F> {
+    text: String,
+    parts: Vec<Part<F>>,
+}
+
+impl<F> FormattedParts<F> {
+    /// Creates the parts of `text`, from the field positions reported by a formatter in
+    /// `positions`.
+    ///
+    /// `to_field` converts the raw field numbers into a typed field.  Fields for which it returns
+    /// `None` are skipped.
+    pub fn try_new(
+        text: &ustring::UChar,
+        positions: UFieldPositionIterator,
+        to_field: impl Fn(i32) -> Option<F>,
+    ) -> Result<FormattedParts<F>, common::Error> {
+        FormattedParts::try_from_utf16_ranges(
+            text,
+            positions.filter_map(|position| {
+                Some((to_field(position.field)?, position.begin..position.end))
+            }),
+        )
+    }
+
+    /// Creates the parts of `text` from typed fields, whose ranges are given in UTF-16 code
+    /// units.  This is useful for formatters that report their fields in some other way than
+    /// through a [UFieldPositionIterator].
+    ///
+    /// Returns an error if a range does not fit within `text`.
+    pub fn try_from_utf16_ranges(
+        text: &ustring::UChar,
+        fields: impl IntoIterator<Item = (F, ops::Range<usize>)>,
+    ) -> Result<FormattedParts<F>, common::Error> {
+        let text = String::try_from(text)?;
+        // Maps each UTF-16 offset to the byte offset in `text`.  Offsets that fall within a
+        // surrogate pair map to the start of the character; ICU does not report those.
+        let mut byte_offsets: Vec<usize> = Vec::with_capacity(text.len() + 1);
+        for (offset, c) in text.char_indices() {
+            for _ in 0..c.len_utf16() {
+                byte_offsets.push(offset);
+            }
+        }
+        byte_offsets.push(text.len());
+
+        let parts = fields
+            .into_iter()
+            .map(|(field, range)| {
+                match (byte_offsets.get(range.start), byte_offsets.get(range.end)) {
+                    (Some(&start), Some(&end)) if start <= end => Ok(Part {
+                        field,
+                        range: start..end,
+                    }),
+                    _ => Err(common::Error::Sys {
+                        function: "FormattedParts::try_from_utf16_ranges",
+                        code: sys::UErrorCode::U_INDEX_OUTOFBOUNDS_ERROR,
+                    }),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(FormattedParts { text, parts })
+    }
+
+    /// Returns the entire formatted text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the parts of the formatted text, in the order reported by the formatter.
+    pub fn parts(&self) -> &[Part<F>] {
+        &self.parts
+    }
+
+    /// Returns the text of `part`, which must be one of the parts of this text.
+    pub fn get(&self, part: &Part<F>) -> &str {
+        &self.text[part.range.clone()]
+    }
+
+    /// Iterates over the fields of this text, together with their text.
+    pub fn iter(&self) -> impl Iterator<Item = (&F, &str)> {
+        self.parts
+            .iter()
+            .map(move |part| (&part.field, self.get(part)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_iterator() -> Result<(), common::Error> {
+        let mut positions = UFieldPositionIterator::try_new()?;
+        assert_eq!(None, positions.next());
+        Ok(())
+    }
+
+    #[test]
+    fn parts_of_empty_text() -> Result<(), common::Error> {
+        let text = ustring::UChar::try_from("")?;
+        let parts = FormattedParts::try_new(&text, UFieldPositionIterator::try_new()?, Some)?;
+        assert_eq!("", parts.text());
+        assert!(parts.parts().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn parts_from_utf16_ranges() -> Result<(), common::Error> {
+        // The emoji takes two UTF-16 code units and four bytes.
+        let text = ustring::UChar::try_from("a😀bc")?;
+        let parts =
+            FormattedParts::try_from_utf16_ranges(&text, vec![("emoji", 1..3), ("bc", 3..5)])?;
+        assert_eq!(
+            vec![(&"emoji", "😀"), (&"bc", "bc")],
+            parts.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(5..7, parts.parts()[1].range);
+        Ok(())
+    }
+
+    #[test]
+    fn ranges_outside_of_text() -> Result<(), common::Error> {
+        let text = ustring::UChar::try_from("abc")?;
+        for range in &[2..4, 4..5] {
+            assert!(
+                FormattedParts::try_from_utf16_ranges(&text, vec![((), range.clone())]).is_err(),
+                "range: {:?}",
+                range
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn field_numbers() {
+        for (number, field) in DATE_FORMAT_FIELDS.iter().enumerate() {
+            assert_eq!(Some(*field), date_format_field(number as i32));
+        }
+        for (number, field) in NUMBER_FORMAT_FIELDS.iter().enumerate() {
+            assert_eq!(Some(*field), number_format_field(number as i32));
+        }
+        assert_eq!(None, date_format_field(-1));
+        assert_eq!(
+            None,
+            date_format_field(sys::UDateFormatField::UDAT_FIELD_COUNT as i32)
+        );
+        assert_eq!(
+            None,
+            number_format_field(sys::UNumberFormatFields::UNUM_FIELD_COUNT as i32)
+        );
+    }
+}
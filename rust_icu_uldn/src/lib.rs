@@ -0,0 +1,461 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU locale display names support for rust
+//!
+//! This crate provides the names of locales, languages, scripts, regions and the like, in the
+//! language of a given locale, as implemented by the ICU library.  Specifically the
+//! functionality exposed through its C API, as available in the [header
+//! `uldn.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/uldn_8h.html).
+//!
+//! [DisplayNames] implements the `ecma402_traits::displaynames::DisplayNames` trait on top of
+//! [ULocaleDisplayNames].
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_icu_sys as sys;
+//! use rust_icu_uldn as uldn;
+//! use rust_icu_uloc as uloc;
+//! use std::convert::TryFrom;
+//!
+//! let ui_locale = uloc::ULoc::try_from("de-DE").expect("locale");
+//! let names = uldn::ULocaleDisplayNames::try_new(
+//!     &ui_locale,
+//!     sys::UDialectHandling::ULDN_STANDARD_NAMES,
+//! )
+//! .expect("display names");
+//! let austria = uloc::ULoc::try_from("de-AT").expect("locale");
+//! assert_eq!(
+//!     "Deutsch (Österreich)",
+//!     names.locale_display_name(&austria).expect("name")
+//! );
+//! ```
+
+use {
+    ecma402_traits::{displaynames, AsBCP47},
+    rust_icu_common as common, rust_icu_sys as sys,
+    rust_icu_sys::versioned_function,
+    rust_icu_sys::*,
+    rust_icu_uloc as uloc, rust_icu_ustring as ustring,
+    std::convert::TryFrom,
+    std::{ffi, fmt, os::raw, ptr},
+};
+
+/// The initial size of the buffers for display names.
+const CAPACITY: usize = 100;
+
+/// Provides the display names of locales and their parts, in the language of a locale.
+///
+/// Implements `ULocaleDisplayNames` from ICU.
+#[derive(Debug)]
+pub struct ULocaleDisplayNames {
+    rep: ptr::NonNull<sys::ULocaleDisplayNames>,
+}
+
+impl Drop for ULocaleDisplayNames {
+    /// Implements `uldn_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(uldn_close)(self.rep.as_ptr()) };
+    }
+}
+
+impl ULocaleDisplayNames {
+    /// Creates display names in the language of `locale`.  `dialect_handling` decides whether
+    /// dialect names such as "Austrian German" are used, or the language and the region are
+    /// named separately, as in "German (Austria)".
+    ///
+    /// Implements `uldn_open`
+    pub fn try_new(
+        locale: &uloc::ULoc,
+        dialect_handling: sys::UDialectHandling,
+    ) -> Result<ULocaleDisplayNames, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let asciiz_locale = locale.as_c_str();
+        // Requires that asciiz_locale is a valid C string, which it is by construction.
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uldn_open)(asciiz_locale.as_ptr(), dialect_handling, &mut status)
+        };
+        common::Error::ok_or_warning("uldn_open", status)?;
+        Ok(ULocaleDisplayNames {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Creates display names in the language of `locale`, with the given display contexts.
+    /// Each context sets one option, such as `UDISPCTX_DIALECT_NAMES`, `UDISPCTX_LENGTH_SHORT`
+    /// or `UDISPCTX_NO_SUBSTITUTE`.  The options that are not given keep their defaults.
+    ///
+    /// Implements `uldn_openForContext`
+    pub fn try_new_for_context(
+        locale: &uloc::ULoc,
+        contexts: &[sys::UDisplayContext],
+    ) -> Result<ULocaleDisplayNames, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let asciiz_locale = locale.as_c_str();
+        // ICU does not modify the contexts, but its signature asks for a mutable pointer.
+        let mut contexts = contexts.to_vec();
+        // Requires that asciiz_locale is a valid C string, and that the length of contexts is
+        // correct, which they are by construction.
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uldn_openForContext)(
+                asciiz_locale.as_ptr(),
+                contexts.as_mut_ptr(),
+                contexts.len() as i32,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("uldn_openForContext", status)?;
+        Ok(ULocaleDisplayNames {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Returns the locale in whose language the display names are given.
+    ///
+    /// Implements `uldn_getLocale`
+    pub fn get_locale(&self) -> Result<uloc::ULoc, common::Error> {
+        // Requires that the returned string is valid for the lifetime of self, which ICU
+        // guarantees.
+        let locale = unsafe {
+            let locale = versioned_function!(uldn_getLocale)(self.rep.as_ptr());
+            ffi::CStr::from_ptr(locale)
+        };
+        uloc::ULoc::try_from(locale)
+    }
+
+    /// Returns how dialect names are handled.
+    ///
+    /// Implements `uldn_getDialectHandling`
+    pub fn get_dialect_handling(&self) -> sys::UDialectHandling {
+        unsafe { versioned_function!(uldn_getDialectHandling)(self.rep.as_ptr()) }
+    }
+
+    /// Returns the display context that is in effect for `context_type`, such as
+    /// `UDISPCTX_TYPE_DISPLAY_LENGTH`.
+    ///
+    /// Implements `uldn_getContext`
+    pub fn get_context(
+        &self,
+        context_type: sys::UDisplayContextType,
+    ) -> Result<sys::UDisplayContext, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let context = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uldn_getContext)(self.rep.as_ptr(), context_type, &mut status)
+        };
+        common::Error::ok_or_warning("uldn_getContext", status)?;
+        Ok(context)
+    }
+
+    /// Returns the display name of `locale`, for example "German (Austria)" for `de-AT`.
+    ///
+    /// Implements `uldn_localeDisplayName`
+    pub fn locale_display_name(&self, locale: &uloc::ULoc) -> Result<String, common::Error> {
+        self.display_name(
+            "uldn_localeDisplayName",
+            versioned_function!(uldn_localeDisplayName),
+            locale.label(),
+        )
+    }
+
+    /// Returns the display name of the language subtag `lang`, for example "German" for `de`.
+    ///
+    /// Implements `uldn_languageDisplayName`
+    pub fn language_display_name(&self, lang: &str) -> Result<String, common::Error> {
+        self.display_name(
+            "uldn_languageDisplayName",
+            versioned_function!(uldn_languageDisplayName),
+            lang,
+        )
+    }
+
+    /// Returns the display name of the script subtag `script`, for example "Cyrillic" for
+    /// `Cyrl`.
+    ///
+    /// Implements `uldn_scriptDisplayName`
+    pub fn script_display_name(&self, script: &str) -> Result<String, common::Error> {
+        self.display_name(
+            "uldn_scriptDisplayName",
+            versioned_function!(uldn_scriptDisplayName),
+            script,
+        )
+    }
+
+    /// Returns the display name of the region subtag `region`, for example "Austria" for `AT`.
+    ///
+    /// Implements `uldn_regionDisplayName`
+    pub fn region_display_name(&self, region: &str) -> Result<String, common::Error> {
+        self.display_name(
+            "uldn_regionDisplayName",
+            versioned_function!(uldn_regionDisplayName),
+            region,
+        )
+    }
+
+    /// Returns the display name of the variant subtag `variant`, for example "German
+    /// Orthography of 1996" for `1996`.
+    ///
+    /// Implements `uldn_variantDisplayName`
+    pub fn variant_display_name(&self, variant: &str) -> Result<String, common::Error> {
+        self.display_name(
+            "uldn_variantDisplayName",
+            versioned_function!(uldn_variantDisplayName),
+            variant,
+        )
+    }
+
+    /// Returns the display name of the legacy locale keyword `key`, for example "Calendar" for
+    /// `calendar`.
+    ///
+    /// Implements `uldn_keyDisplayName`
+    pub fn key_display_name(&self, key: &str) -> Result<String, common::Error> {
+        self.display_name(
+            "uldn_keyDisplayName",
+            versioned_function!(uldn_keyDisplayName),
+            key,
+        )
+    }
+
+    /// Returns the display name of `value` of the legacy locale keyword `key`, for example
+    /// "Gregorian Calendar" for `gregorian` of `calendar`.  The key `currency` gives the names
+    /// of currencies, for example "Euro" for `EUR`.
+    ///
+    /// Implements `uldn_keyValueDisplayName`
+    pub fn key_value_display_name(&self, key: &str, value: &str) -> Result<String, common::Error> {
+        common::buffered_uchar_method_with_retry!(
+            key_value_display_name,
+            CAPACITY,
+            [
+                ldn: *const sys::ULocaleDisplayNames,
+                key: *const raw::c_char,
+                value: *const raw::c_char,
+            ],
+            []
+        );
+        let key = ffi::CString::new(key)?;
+        let value = ffi::CString::new(value)?;
+        let result = key_value_display_name(
+            "uldn_keyValueDisplayName",
+            versioned_function!(uldn_keyValueDisplayName),
+            self.rep.as_ptr(),
+            key.as_ptr(),
+            value.as_ptr(),
+        )?;
+        String::try_from(&result)
+    }
+
+    // Calls one of the uldn_*DisplayName functions that take a single code.
+    fn display_name(
+        &self,
+        function: &'static str,
+        method: unsafe extern "C" fn(
+            *const sys::ULocaleDisplayNames,
+            *const raw::c_char,
+            *mut sys::UChar,
+            i32,
+            *mut sys::UErrorCode,
+        ) -> i32,
+        code: &str,
+    ) -> Result<String, common::Error> {
+        common::buffered_uchar_method_with_retry!(
+            display_name,
+            CAPACITY,
+            [ldn: *const sys::ULocaleDisplayNames, code: *const raw::c_char,],
+            []
+        );
+        let code = ffi::CString::new(code)?;
+        let result = display_name(function, method, self.rep.as_ptr(), code.as_ptr())?;
+        String::try_from(&result)
+    }
+}
+
+/// An implementation of `Intl.DisplayNames` from ECMA 402, based on [ULocaleDisplayNames].
+#[derive(Debug)]
+pub struct DisplayNames {
+    names: ULocaleDisplayNames,
+    opts: displaynames::Options,
+}
+
+impl displaynames::DisplayNames for DisplayNames {
+    type Error = common::Error;
+
+    /// Implements `uldn_openForContext`
+    fn try_new<L>(locale: L, opts: displaynames::Options) -> Result<Self, Self::Error>
+    where
+        L: AsBCP47,
+    {
+        use displaynames::options::*;
+        let locale = uloc::ULoc::for_language_tag(&locale.as_bcp47())?;
+        let contexts = [
+            match opts.language_display {
+                LanguageDisplay::Dialect => UDisplayContext::UDISPCTX_DIALECT_NAMES,
+                LanguageDisplay::Standard => UDisplayContext::UDISPCTX_STANDARD_NAMES,
+            },
+            // ICU has no narrow display names.
+            match opts.style {
+                Style::Narrow | Style::Short => UDisplayContext::UDISPCTX_LENGTH_SHORT,
+                Style::Long => UDisplayContext::UDISPCTX_LENGTH_FULL,
+            },
+            match opts.fallback {
+                Fallback::Code => UDisplayContext::UDISPCTX_SUBSTITUTE,
+                Fallback::None => UDisplayContext::UDISPCTX_NO_SUBSTITUTE,
+            },
+        ];
+        let names = ULocaleDisplayNames::try_new_for_context(&locale, &contexts)?;
+        Ok(DisplayNames { names, opts })
+    }
+
+    fn of<W>(&self, code: &str, writer: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        use displaynames::options::*;
+        let name = match self.opts.in_type {
+            Type::Language => uloc::ULoc::for_language_tag(code)
+                .and_then(|locale| self.names.locale_display_name(&locale)),
+            Type::Region => self.names.region_display_name(code),
+            Type::Script => self.names.script_display_name(code),
+            Type::Currency => self.names.key_value_display_name("currency", code),
+            Type::Calendar => {
+                let calendar = uloc::to_legacy_type("ca", code).unwrap_or_else(|| code.to_string());
+                self.names.key_value_display_name("calendar", &calendar)
+            }
+        };
+        match name {
+            Ok(name) => writer.write_str(&name),
+            // ICU reports a missing display name as an illegal argument when asked not to
+            // substitute it.  Any other error, such as a malformed code, is still an error.
+            Err(common::Error::Sys {
+                function,
+                code: sys::UErrorCode::U_ILLEGAL_ARGUMENT_ERROR,
+            }) if self.opts.fallback == Fallback::None && function.starts_with("uldn_") => Ok(()),
+            Err(_) => Err(fmt::Error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, displaynames::DisplayNames as _, sys::UDialectHandling::*};
+
+    #[test]
+    fn dialect_handling() -> Result<(), common::Error> {
+        let ui_locale = uloc::ULoc::try_from("en-US")?;
+        let austria = uloc::ULoc::try_from("de-AT")?;
+
+        let names = ULocaleDisplayNames::try_new(&ui_locale, ULDN_STANDARD_NAMES)?;
+        assert_eq!(ULDN_STANDARD_NAMES, names.get_dialect_handling());
+        assert_eq!("German (Austria)", names.locale_display_name(&austria)?);
+
+        let names = ULocaleDisplayNames::try_new(&ui_locale, ULDN_DIALECT_NAMES)?;
+        assert_eq!(ULDN_DIALECT_NAMES, names.get_dialect_handling());
+        assert_eq!("Austrian German", names.locale_display_name(&austria)?);
+        assert_eq!(ui_locale, names.get_locale()?);
+        Ok(())
+    }
+
+    #[test]
+    fn subtag_names() -> Result<(), common::Error> {
+        let ui_locale = uloc::ULoc::try_from("fr-FR")?;
+        let names = ULocaleDisplayNames::try_new(&ui_locale, ULDN_STANDARD_NAMES)?;
+        assert_eq!("allemand", names.language_display_name("de")?);
+        assert_eq!("cyrillique", names.script_display_name("Cyrl")?);
+        assert_eq!("Autriche", names.region_display_name("AT")?);
+        assert_eq!(
+            "orthographe allemande de 1996",
+            names.variant_display_name("1996")?
+        );
+        assert_eq!("calendrier", names.key_display_name("calendar")?);
+        assert_eq!(
+            "calendrier grégorien",
+            names.key_value_display_name("calendar", "gregorian")?
+        );
+        assert_eq!("euro", names.key_value_display_name("currency", "EUR")?);
+        Ok(())
+    }
+
+    #[test]
+    fn contexts() -> Result<(), common::Error> {
+        let ui_locale = uloc::ULoc::try_from("en-US")?;
+        let names = ULocaleDisplayNames::try_new_for_context(
+            &ui_locale,
+            &[UDisplayContext::UDISPCTX_LENGTH_SHORT],
+        )?;
+        assert_eq!(
+            UDisplayContext::UDISPCTX_LENGTH_SHORT,
+            names.get_context(UDisplayContextType::UDISPCTX_TYPE_DISPLAY_LENGTH)?
+        );
+        assert_eq!("US", names.region_display_name("US")?);
+        Ok(())
+    }
+
+    #[test]
+    fn ecma402_display_names() -> Result<(), common::Error> {
+        use displaynames::options::*;
+        let ui_locale = uloc::ULoc::try_from("de-DE")?;
+        let tests = vec![
+            (
+                Type::Language,
+                Fallback::Code,
+                "de-AT",
+                "Österreichisches Deutsch",
+            ),
+            (Type::Region, Fallback::Code, "AT", "Österreich"),
+            (Type::Script, Fallback::Code, "Cyrl", "Kyrillisch"),
+            (Type::Currency, Fallback::Code, "EUR", "Euro"),
+            (
+                Type::Calendar,
+                Fallback::Code,
+                "gregory",
+                "Gregorianischer Kalender",
+            ),
+            (Type::Region, Fallback::Code, "ZZZ", "ZZZ"),
+            (Type::Region, Fallback::None, "ZZZ", ""),
+        ];
+        for (in_type, fallback, code, expected) in tests {
+            let opts = displaynames::Options {
+                in_type,
+                fallback,
+                ..Default::default()
+            };
+            let names = DisplayNames::try_new(&ui_locale, opts)?;
+            let mut name = String::new();
+            names.of(code, &mut name).expect("formatted");
+            assert_eq!(expected, name, "code: {}", code);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn ecma402_malformed_code_is_an_error() -> Result<(), common::Error> {
+        use displaynames::options::*;
+        let ui_locale = uloc::ULoc::try_from("de-DE")?;
+        let opts = displaynames::Options {
+            in_type: Type::Region,
+            fallback: Fallback::None,
+            ..Default::default()
+        };
+        let names = DisplayNames::try_new(&ui_locale, opts)?;
+        let mut name = String::new();
+        assert_eq!(Err(fmt::Error), names.of("A\0T", &mut name));
+        Ok(())
+    }
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use {
+    anyhow::anyhow,
     rust_icu_common as common,
     rust_icu_common::buffered_string_method_with_retry,
     rust_icu_sys::versioned_function,
@@ -70,40 +71,61 @@ impl TryFrom<&ffi::CStr> for ULoc {
 impl ULoc {
     /// Implements `uloc_getLanguage`.
     pub fn language(&self) -> Option<String> {
-        self.call_buffered_string_method_to_option(versioned_function!(uloc_getLanguage))
+        self.call_buffered_string_method_to_option(
+            "uloc_getLanguage",
+            versioned_function!(uloc_getLanguage),
+        )
     }
 
     /// Implements `uloc_getScript`.
     pub fn script(&self) -> Option<String> {
-        self.call_buffered_string_method_to_option(versioned_function!(uloc_getScript))
+        self.call_buffered_string_method_to_option(
+            "uloc_getScript",
+            versioned_function!(uloc_getScript),
+        )
     }
 
     /// Implements `uloc_getCountry`.
     pub fn country(&self) -> Option<String> {
-        self.call_buffered_string_method_to_option(versioned_function!(uloc_getCountry))
+        self.call_buffered_string_method_to_option(
+            "uloc_getCountry",
+            versioned_function!(uloc_getCountry),
+        )
     }
 
     /// Implements `uloc_getVariant`.
     pub fn variant(&self) -> Option<String> {
-        self.call_buffered_string_method_to_option(versioned_function!(uloc_getVariant))
+        self.call_buffered_string_method_to_option(
+            "uloc_getVariant",
+            versioned_function!(uloc_getVariant),
+        )
     }
 
     /// Implements `uloc_canonicalize` from ICU4C.
     pub fn canonicalize(&self) -> Result<ULoc, common::Error> {
-        self.call_buffered_string_method(versioned_function!(uloc_canonicalize))
-            .map(|repr| ULoc { repr })
+        self.call_buffered_string_method(
+            "uloc_canonicalize",
+            versioned_function!(uloc_canonicalize),
+        )
+        .map(|repr| ULoc { repr })
     }
 
     /// Implements `uloc_addLikelySubtags` from ICU4C.
     pub fn add_likely_subtags(&self) -> Result<ULoc, common::Error> {
-        self.call_buffered_string_method(versioned_function!(uloc_addLikelySubtags))
-            .map(|repr| ULoc { repr })
+        self.call_buffered_string_method(
+            "uloc_addLikelySubtags",
+            versioned_function!(uloc_addLikelySubtags),
+        )
+        .map(|repr| ULoc { repr })
     }
 
     /// Implements `uloc_minimizeSubtags` from ICU4C.
     pub fn minimize_subtags(&self) -> Result<ULoc, common::Error> {
-        self.call_buffered_string_method(versioned_function!(uloc_minimizeSubtags))
-            .map(|repr| ULoc { repr })
+        self.call_buffered_string_method(
+            "uloc_minimizeSubtags",
+            versioned_function!(uloc_minimizeSubtags),
+        )
+        .map(|repr| ULoc { repr })
     }
 
     /// Implements `uloc_toLanguageTag` from ICU4C.
@@ -119,10 +141,12 @@ impl ULoc {
         // No `UBool` constants available in rust_icu_sys, unfortunately.
         let strict = if strict { 1 } else { 0 };
         buffered_string_to_language_tag(
+            "uloc_toLanguageTag",
             versioned_function!(uloc_toLanguageTag),
             locale_id.as_ptr(),
             strict,
         )
+        .map_err(|e| e.for_locale(&self.repr))
     }
 
     /// Implements `uloc_openKeywords()` from ICU4C.
@@ -151,6 +175,7 @@ impl ULoc {
         let locale_id = self.as_c_str();
         let keyword_name = str_to_cstring(keyword);
         buffered_string_keyword_value(
+            "uloc_getKeywordValue",
             versioned_function!(uloc_getKeywordValue),
             locale_id.as_ptr(),
             keyword_name.as_ptr(),
@@ -176,6 +201,157 @@ impl ULoc {
         }
     }
 
+    /// Returns the unicode keywords of this locale, together with their values.  For example
+    /// `("ca", "hebrew")` for the locale `az-u-ca-hebrew`.
+    ///
+    /// Keywords that do not have a unicode representation are skipped.  An error is returned for
+    /// a keyword whose value can not be read.
+    pub fn unicode_keyword_values(
+        &self,
+    ) -> impl Iterator<Item = Result<(String, String), common::Error>> + '_ {
+        self.keywords().filter_map(move |legacy_keyword| {
+            let legacy_value = match self.keyword_value(&legacy_keyword) {
+                Ok(Some(legacy_value)) => legacy_value,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            Some(Ok((
+                to_unicode_locale_key(&legacy_keyword)?,
+                to_unicode_locale_type(&legacy_keyword, &legacy_value)?,
+            )))
+        })
+    }
+
+    /// Sets the keyword `keyword` to `value`.  If `value` is `None`, the keyword is removed from
+    /// this locale.
+    ///
+    /// Implements `uloc_setKeywordValue()` from ICU4C.
+    pub fn set_keyword_value(
+        &mut self,
+        keyword: &str,
+        value: Option<&str>,
+    ) -> Result<(), common::Error> {
+        // The result is usually no longer than the current ID with "@keyword=value" appended,
+        // plus NUL.  ICU canonicalizes the keywords though, so this is only a first guess.
+        let capacity = self.repr.len() + keyword.len() + value.map_or(0, str::len) + 3;
+        self.set_keyword_value_with_capacity(keyword, value, capacity)
+    }
+
+    // Implements `set_keyword_value`, starting with a buffer of `capacity` bytes, which is grown
+    // as ICU asks for more.
+    fn set_keyword_value_with_capacity(
+        &mut self,
+        keyword: &str,
+        value: Option<&str>,
+        capacity: usize,
+    ) -> Result<(), common::Error> {
+        let keyword_name = str_to_cstring(keyword);
+        let keyword_value = value.map(str_to_cstring);
+        // The buffer must at least hold the current ID, which ICU reads from it.
+        let mut capacity = std::cmp::max(capacity, self.repr.len() + 1);
+        loop {
+            // The buffer holds the locale ID on input, and the modified locale ID on output.
+            let mut buf: Vec<u8> = self.repr.clone().into_bytes();
+            buf.resize(capacity, 0);
+            let mut status = common::Error::OK_CODE;
+            let len = unsafe {
+                assert!(common::Error::is_ok(status));
+                versioned_function!(uloc_setKeywordValue)(
+                    keyword_name.as_ptr(),
+                    keyword_value
+                        .as_ref()
+                        .map_or(std::ptr::null(), |value| value.as_ptr()),
+                    buf.as_mut_ptr() as *mut raw::c_char,
+                    capacity as i32,
+                    &mut status,
+                )
+            };
+            // ICU returns the length it needs if the buffer is too small, and does not NUL
+            // terminate a result that fills the buffer exactly.  Try again with enough room.
+            if status == UErrorCode::U_BUFFER_OVERFLOW_ERROR
+                || status == UErrorCode::U_STRING_NOT_TERMINATED_WARNING
+            {
+                capacity = std::cmp::max(len as usize, capacity) + 1;
+                continue;
+            }
+            common::Error::ok_or_warning("uloc_setKeywordValue", status)?;
+            buf.truncate(len as usize);
+            self.repr = String::from_utf8(buf)?;
+            return Ok(());
+        }
+    }
+
+    /// Sets the unicode keyword `unicode_keyword` to `unicode_value`.  If `unicode_value` is
+    /// `None`, the keyword is removed from this locale.
+    ///
+    /// Implements `icu::Locale::setUnicodeKeywordValue()` from the C++ API.
+    pub fn set_unicode_keyword_value(
+        &mut self,
+        unicode_keyword: &str,
+        unicode_value: Option<&str>,
+    ) -> Result<(), common::Error> {
+        let illegal_argument = |function| common::Error::Sys {
+            function,
+            code: UErrorCode::U_ILLEGAL_ARGUMENT_ERROR,
+        };
+        let legacy_keyword =
+            to_legacy_key(unicode_keyword).ok_or_else(|| illegal_argument("uloc_toLegacyKey"))?;
+        let legacy_value = match unicode_value {
+            Some(unicode_value) => Some(
+                to_legacy_type(unicode_keyword, unicode_value)
+                    .ok_or_else(|| illegal_argument("uloc_toLegacyType"))?,
+            ),
+            None => None,
+        };
+        self.set_keyword_value(&legacy_keyword, legacy_value.as_deref())
+    }
+
+    /// Returns the calendar requested by this locale (the `-u-ca` keyword), e.g. `gregory`.
+    pub fn calendar(&self) -> Result<Option<String>, common::Error> {
+        self.unicode_keyword_value("ca")
+    }
+
+    /// Sets or removes the calendar requested by this locale (the `-u-ca` keyword).
+    pub fn set_calendar(&mut self, calendar: Option<&str>) -> Result<(), common::Error> {
+        self.set_unicode_keyword_value("ca", calendar)
+    }
+
+    /// Returns the collation requested by this locale (the `-u-co` keyword), e.g. `phonebk`.
+    pub fn collation(&self) -> Result<Option<String>, common::Error> {
+        self.unicode_keyword_value("co")
+    }
+
+    /// Sets or removes the collation requested by this locale (the `-u-co` keyword).
+    pub fn set_collation(&mut self, collation: Option<&str>) -> Result<(), common::Error> {
+        self.set_unicode_keyword_value("co", collation)
+    }
+
+    /// Returns the numbering system requested by this locale (the `-u-nu` keyword), e.g. `arab`.
+    pub fn numbering_system(&self) -> Result<Option<String>, common::Error> {
+        self.unicode_keyword_value("nu")
+    }
+
+    /// Sets or removes the numbering system requested by this locale (the `-u-nu` keyword).
+    pub fn set_numbering_system(
+        &mut self,
+        numbering_system: Option<&str>,
+    ) -> Result<(), common::Error> {
+        self.set_unicode_keyword_value("nu", numbering_system)
+    }
+
+    /// Returns the hour cycle requested by this locale (the `-u-hc` keyword).
+    pub fn hour_cycle(&self) -> Result<Option<HourCycle>, common::Error> {
+        match self.unicode_keyword_value("hc")? {
+            Some(value) => Ok(Some(HourCycle::try_from(&value[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Sets or removes the hour cycle requested by this locale (the `-u-hc` keyword).
+    pub fn set_hour_cycle(&mut self, hour_cycle: Option<HourCycle>) -> Result<(), common::Error> {
+        self.set_unicode_keyword_value("hc", hour_cycle.map(HourCycle::as_str))
+    }
+
     /// Returns the current label of this locale.
     pub fn label(&self) -> &str {
         &self.repr
@@ -186,6 +362,9 @@ impl ULoc {
         ffi::CString::new(self.repr.clone()).expect("ULoc contained interior NUL bytes")
     }
 
+    /// Creates a locale from the BCP 47 language tag `tag`, such as `en-US`.  ICU stops at the
+    /// first subtag that is not well formed, and returns the locale parsed up to there.
+    ///
     /// Implements `uloc_forLanguageTag` from ICU4C.
     pub fn for_language_tag(tag: &str) -> Result<ULoc, common::Error> {
         buffered_string_method_with_retry!(
@@ -195,18 +374,21 @@ impl ULoc {
             [parsed_length: *mut i32,]
         );
 
-        let tag = str_to_cstring(tag);
+        let asciiz_tag = str_to_cstring(tag);
         let locale_id = buffered_string_for_language_tag(
+            "uloc_forLanguageTag",
             versioned_function!(uloc_forLanguageTag),
-            tag.as_ptr(),
+            asciiz_tag.as_ptr(),
             std::ptr::null_mut(),
-        )?;
+        )
+        .map_err(|e| e.for_locale(tag))?;
         ULoc::try_from(&locale_id[..])
     }
 
     /// Call a `uloc` method that takes this locale's ID and returns a string.
     fn call_buffered_string_method(
         &self,
+        function: &'static str,
         uloc_method: unsafe extern "C" fn(
             *const raw::c_char,
             *mut raw::c_char,
@@ -221,13 +403,15 @@ impl ULoc {
             []
         );
         let asciiz = self.as_c_str();
-        buffered_string_char_star(uloc_method, asciiz.as_ptr())
+        buffered_string_char_star(function, uloc_method, asciiz.as_ptr())
+            .map_err(|e| e.for_locale(&self.repr))
     }
 
     /// Call a `uloc` method that takes this locale's ID, panics on any errors, and returns
     /// `Some(result)` if the resulting string is non-empty, or `None` otherwise.
     fn call_buffered_string_method_to_option(
         &self,
+        function: &'static str,
         uloc_method: unsafe extern "C" fn(
             *const raw::c_char,
             *mut raw::c_char,
@@ -235,7 +419,9 @@ impl ULoc {
             *mut UErrorCode,
         ) -> i32,
     ) -> Option<String> {
-        let value: String = self.call_buffered_string_method(uloc_method).unwrap();
+        let value: String = self
+            .call_buffered_string_method(function, uloc_method)
+            .unwrap();
         if value.is_empty() {
             None
         } else {
@@ -244,6 +430,75 @@ impl ULoc {
     }
 }
 
+impl ecma402_traits::AsBCP47 for ULoc {
+    /// Converts this locale into a language tag.  The conversion is not strict: any parts of the
+    /// locale that can not be represented in BCP 47 are dropped.
+    ///
+    /// If ICU can not convert the locale at all, the locale identifier, as returned by
+    /// [ULoc::label], is used instead.
+    fn as_bcp47(&self) -> String {
+        self.to_language_tag(false)
+            .unwrap_or_else(|_| self.label().to_string())
+    }
+}
+
+impl ecma402_traits::LanguageIdentifier for ULoc {
+    fn language(&self) -> Option<String> {
+        ULoc::language(self)
+    }
+
+    fn script(&self) -> Option<String> {
+        ULoc::script(self)
+    }
+
+    fn region(&self) -> Option<String> {
+        ULoc::country(self)
+    }
+}
+
+/// The hour cycle of a locale, as set in the `-u-hc` keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HourCycle {
+    /// Hours 0 to 11, as in "0:30 PM".
+    H11,
+    /// Hours 1 to 12, as in "12:30 PM".
+    H12,
+    /// Hours 0 to 23, as in "0:30".
+    H23,
+    /// Hours 1 to 24, as in "24:30".
+    H24,
+}
+
+impl HourCycle {
+    /// Returns the unicode keyword value for this hour cycle, e.g. `h23`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HourCycle::H11 => "h11",
+            HourCycle::H12 => "h12",
+            HourCycle::H23 => "h23",
+            HourCycle::H24 => "h24",
+        }
+    }
+}
+
+impl TryFrom<&str> for HourCycle {
+    type Error = common::Error;
+
+    /// Parses the unicode keyword value of an hour cycle, e.g. `h23`.
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "h11" => Ok(HourCycle::H11),
+            "h12" => Ok(HourCycle::H12),
+            "h23" => Ok(HourCycle::H23),
+            "h24" => Ok(HourCycle::H24),
+            _ => Err(common::Error::wrapper(anyhow!(
+                "not an hour cycle: {:?}",
+                s
+            ))),
+        }
+    }
+}
+
 /// This implementation is based on ULocale.compareTo from ICU4J.
 /// See https://github.com/unicode-org/icu/blob/master/icu4j/main/classes/core/src/com/ibm/icu/util/ULocale.java
 impl Ord for ULoc {
@@ -326,7 +581,7 @@ pub fn set_default(loc: &ULoc) -> Result<(), common::Error> {
     let mut status = common::Error::OK_CODE;
     let asciiz = str_to_cstring(&loc.repr);
     unsafe { versioned_function!(uloc_setDefault)(asciiz.as_ptr(), &mut status) };
-    common::Error::ok_or_warning(status)
+    common::Error::ok_or_warning("uloc_setDefault", status)
 }
 
 /// Implements `uloc_acceptLanguage` from ICU4C.
@@ -369,6 +624,7 @@ pub fn accept_language(
     let mut available_locales = Enumeration::try_from(&available_locales[..])?;
 
     let matched_locale = buffered_string_uloc_accept_language(
+        "uloc_acceptLanguage",
         versioned_function!(uloc_acceptLanguage),
         &mut accept_result,
         accept_list.as_mut_ptr(),
@@ -420,6 +676,20 @@ pub fn to_legacy_key(unicode_keyword: &str) -> Option<String> {
     legacy_keyword.map(|cstring| cstring_to_string(&cstring))
 }
 
+/// Implements `uloc_toLegacyType` from ICU4C.
+pub fn to_legacy_type(unicode_keyword: &str, unicode_value: &str) -> Option<String> {
+    let unicode_keyword = str_to_cstring(unicode_keyword);
+    let unicode_value = str_to_cstring(unicode_value);
+    let legacy_value: Option<ffi::CString> = unsafe {
+        let ptr = versioned_function!(uloc_toLegacyType)(
+            unicode_keyword.as_ptr(),
+            unicode_value.as_ptr(),
+        );
+        ptr.as_ref().map(|ptr| ffi::CStr::from_ptr(ptr).to_owned())
+    };
+    legacy_value.map(|cstring| cstring_to_string(&cstring))
+}
+
 /// Infallibly converts a Rust string to a `CString`. If there's an interior NUL, the string is
 /// truncated up to that point.
 fn str_to_cstring(input: &str) -> ffi::CString {
@@ -602,6 +872,97 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_unicode_keyword_values() -> Result<(), Error> {
+        let loc = ULoc::for_language_tag("az-Cyrl-AZ-u-ca-hebrew-co-phonebk-nu-deva")?;
+        let values = loc
+            .unicode_keyword_values()
+            .collect::<Result<Vec<(String, String)>, _>>()?;
+        assert_eq!(
+            values,
+            vec![
+                ("ca".to_string(), "hebrew".to_string()),
+                ("co".to_string(), "phonebk".to_string()),
+                ("nu".to_string(), "deva".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_keyword_value() -> Result<(), Error> {
+        let mut loc = ULoc::try_from("de_DE")?;
+        loc.set_keyword_value("collation", Some("phonebook"))?;
+        assert_eq!(loc.label(), "de_DE@collation=phonebook");
+        loc.set_keyword_value("calendar", Some("buddhist"))?;
+        assert_eq!(loc.label(), "de_DE@calendar=buddhist;collation=phonebook");
+        loc.set_keyword_value("collation", None)?;
+        assert_eq!(loc.label(), "de_DE@calendar=buddhist");
+        loc.set_keyword_value("calendar", None)?;
+        assert_eq!(loc.label(), "de_DE");
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_keyword_value_grows_buffer() -> Result<(), Error> {
+        let mut loc = ULoc::try_from("de_DE@collation=phonebook")?;
+        loc.set_keyword_value_with_capacity("calendar", Some("buddhist"), 0)?;
+        assert_eq!(loc.label(), "de_DE@calendar=buddhist;collation=phonebook");
+        // Exactly the length of the result, without room for the NUL.
+        let mut loc = ULoc::try_from("de_DE")?;
+        loc.set_keyword_value_with_capacity("calendar", Some("buddhist"), 23)?;
+        assert_eq!(loc.label(), "de_DE@calendar=buddhist");
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_keywords() -> Result<(), Error> {
+        let mut loc = ULoc::for_language_tag("en-US-u-ca-buddhist-hc-h23")?;
+        assert_eq!(loc.calendar()?, Some("buddhist".to_string()));
+        assert_eq!(loc.collation()?, None);
+        assert_eq!(loc.numbering_system()?, None);
+        assert_eq!(loc.hour_cycle()?, Some(HourCycle::H23));
+
+        loc.set_calendar(None)?;
+        loc.set_collation(Some("phonebk"))?;
+        loc.set_numbering_system(Some("arab"))?;
+        loc.set_hour_cycle(Some(HourCycle::H12))?;
+        assert_eq!(loc.calendar()?, None);
+        assert_eq!(loc.collation()?, Some("phonebk".to_string()));
+        assert_eq!(loc.numbering_system()?, Some("arab".to_string()));
+        assert_eq!(loc.hour_cycle()?, Some(HourCycle::H12));
+        assert_eq!(
+            loc.to_language_tag(true)?,
+            "en-US-u-co-phonebk-hc-h12-nu-arab"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_unicode_keyword_value_unknown_key() -> Result<(), Error> {
+        let mut loc = ULoc::try_from("en_US")?;
+        assert!(loc
+            .set_unicode_keyword_value("not a key", Some("x"))
+            .is_err());
+        assert_eq!(loc.label(), "en_US");
+        Ok(())
+    }
+
+    #[test]
+    fn test_ecma402_traits() -> Result<(), Error> {
+        use ecma402_traits::{AsBCP47, LanguageIdentifier};
+        fn subtags(id: &impl LanguageIdentifier) -> (Option<String>, Option<String>) {
+            (id.script(), id.region())
+        }
+        let loc = ULoc::try_from("sr_Cyrl_RS@calendar=buddhist")?;
+        assert_eq!(loc.as_bcp47(), "sr-Cyrl-RS-u-ca-buddhist");
+        assert_eq!(
+            subtags(&loc),
+            (Some("Cyrl".to_string()), Some("RS".to_string()))
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_order() -> Result<(), Error> {
         assert!(ULoc::for_language_tag("az")? < ULoc::for_language_tag("az-Cyrl")?);
@@ -740,6 +1101,15 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_partly_parsed_language_tag() -> Result<(), Error> {
+        assert_eq!(
+            ULoc::try_from("en-US")?,
+            ULoc::for_language_tag("en-US-!!")?
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_to_legacy_key() -> Result<(), Error> {
         let actual = to_legacy_key("ca");
@@ -747,6 +1117,13 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_to_legacy_type() -> Result<(), Error> {
+        let actual = to_legacy_type("co", "phonebk");
+        assert_eq!(actual, Some("phonebook".to_string()));
+        Ok(())
+    }
+
     #[test]
     fn test_str_to_cstring() -> Result<(), Error> {
         assert_eq!(str_to_cstring("abc"), ffi::CString::new("abc")?);
@@ -32,6 +32,10 @@
 //! > **Note:** The `MessageFormat` library does not handle loading the format patterns in the
 //! > appropriate language.  This task is left to the application author.
 //!
+//! The [message_format!] macro takes positional arguments whose types are known at compile time.
+//! For patterns with named arguments, such as translations loaded at runtime, use
+//! [NamedMessageFormat] instead, which takes its arguments from a map.
+//!
 //! # Example use
 //!
 //! The example below shows how to format values into an English text.  For more detail about
@@ -89,10 +93,14 @@
 //! ```
 
 use {
-    anyhow::anyhow, rust_icu_common as common, rust_icu_sys as sys, rust_icu_sys::*,
-    rust_icu_uloc as uloc, rust_icu_ustring as ustring, std::convert::TryFrom,
+    rust_icu_common as common, rust_icu_sys as sys, rust_icu_sys::*, rust_icu_uloc as uloc,
+    rust_icu_ustring as ustring, std::convert::TryFrom,
 };
 
+mod named;
+
+pub use named::{ArgType, MessageArg, NamedMessageFormat};
+
 /// A zero-value parse error, used to initialize types that get passed into FFI code.
 static NO_PARSE_ERROR: sys::UParseError = sys::UParseError {
     line: 0,
@@ -101,18 +109,6 @@ static NO_PARSE_ERROR: sys::UParseError = sys::UParseError {
     postContext: [0; 16usize],
 };
 
-/// Converts a parse error to a Result.
-fn parse_ok(e: sys::UParseError) -> Result<(), common::Error> {
-    if e == NO_PARSE_ERROR {
-        return Ok(());
-    }
-    Err(common::Error::Wrapper(anyhow!(
-        "parse error: line: {}, offset: {}",
-        e.line,
-        e.offset
-    )))
-}
-
 /// The implementation of the ICU `UMessageFormat*`.
 ///
 /// Use the [UMessageFormat::try_from] to create a message formatter for a given message pattern in
@@ -189,8 +185,7 @@ impl UMessageFormat {
                 &mut status,
             )
         };
-        common::Error::ok_or_warning(status)?;
-        parse_ok(parse_status)?;
+        common::Error::ok_or_parse_error("umsg_open", status, &parse_status)?;
         Ok(UMessageFormat {
             rep: std::rc::Rc::new(Rep { rep }),
         })
@@ -386,7 +381,7 @@ pub unsafe extern "C" fn format_varargs(
             )
         })
     } as usize;
-    common::Error::ok_or_warning(status)?;
+    common::Error::ok_or_warning("umsg_vformat", status)?;
     result.resize(total_size as usize);
     if total_size > CAP {
         args.with_copy(|va_list| {
@@ -401,7 +396,7 @@ pub unsafe extern "C" fn format_varargs(
                 &mut status,
             );
         });
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("umsg_vformat", status)?;
     }
     String::try_from(&result)
 }
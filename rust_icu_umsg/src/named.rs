@@ -0,0 +1,759 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Message formatting with named arguments, given at runtime.
+//!
+//! The C API can only format positional arguments passed as variadic parameters, so their number
+//! and types must be known at compile time.  [NamedMessageFormat] instead splits the pattern
+//! into its arguments up front, and formats each of them on its own with ICU.  The plural and
+//! select arguments are resolved here, so that arguments nested in them work as well.
+
+use {
+    crate::{message_format, UMessageFormat},
+    anyhow::anyhow,
+    rust_icu_common as common, rust_icu_sys as sys, rust_icu_uloc as uloc,
+    rust_icu_ustring as ustring,
+    std::{
+        collections::{BTreeMap, HashMap},
+        convert::TryFrom,
+    },
+};
+
+/// The value of a single named message argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageArg {
+    /// A string, which can be used for arguments without a type, and for `select`.
+    String(String),
+    /// A number, which can be used for arguments without a type, and for `number`, `plural`,
+    /// `selectordinal`, `choice`, `spellout`, `ordinal` and `duration`.
+    Number(f64),
+    /// A point in time, which can be used for `date` and `time`.
+    Date(sys::UDate),
+}
+
+impl From<&str> for MessageArg {
+    fn from(s: &str) -> Self {
+        MessageArg::String(s.to_string())
+    }
+}
+
+impl From<String> for MessageArg {
+    fn from(s: String) -> Self {
+        MessageArg::String(s)
+    }
+}
+
+impl From<f64> for MessageArg {
+    fn from(n: f64) -> Self {
+        MessageArg::Number(n)
+    }
+}
+
+impl From<i32> for MessageArg {
+    fn from(n: i32) -> Self {
+        MessageArg::Number(n.into())
+    }
+}
+
+/// The kind of value that a pattern expects for an argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArgType {
+    /// The argument has no type, such as `{name}`, and may be a string or a number.
+    Any,
+    /// A [MessageArg::String] is expected.
+    String,
+    /// A [MessageArg::Number] is expected.
+    Number,
+    /// A [MessageArg::Date] is expected.
+    Date,
+}
+
+/// A message formatter for patterns with named arguments, such as `{count, plural, one {# file}
+/// other {# files}}`, which takes its arguments from a map at runtime.
+///
+/// This makes it possible to safely format patterns that are not known at compile time, for
+/// example translations loaded from files: the values of the arguments are checked against the
+/// types that the pattern expects, and an error is returned if they do not match.  Use
+/// [NamedMessageFormat::arguments] to find out which arguments a pattern expects.
+///
+/// The `plural`, `selectordinal` and `select` arguments may contain other arguments, and may be
+/// nested in one another.  The sub-messages of the deprecated `choice` arguments are passed to ICU
+/// as they are, so they may not contain named arguments.
+///
+/// ```
+/// use {
+///     rust_icu_common as common, rust_icu_uloc as uloc,
+///     rust_icu_umsg::{MessageArg, NamedMessageFormat},
+///     std::{collections::HashMap, convert::TryFrom},
+/// };
+///
+/// fn testfn() -> Result<(), common::Error> {
+///     let loc = uloc::ULoc::try_from("en-US")?;
+///     let fmt = NamedMessageFormat::try_new(
+///         "{host} shared {count, plural, one {a photo} other {# photos}} with you.",
+///         &loc,
+///     )?;
+///     let mut args = HashMap::new();
+///     args.insert("host", MessageArg::from("Ana"));
+///     args.insert("count", MessageArg::from(1234));
+///     assert_eq!("Ana shared 1,234 photos with you.", fmt.format(&args)?);
+///     Ok(())
+/// }
+/// # fn main() -> Result<(), common::Error> {
+/// #   testfn()
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct NamedMessageFormat {
+    parts: Vec<Part>,
+    arguments: BTreeMap<String, ArgType>,
+    // Formats numbers in untyped arguments, and in place of `#` in plural sub-messages.
+    number: UMessageFormat,
+}
+
+// A piece of a parsed message.
+#[derive(Debug)]
+enum Part {
+    Text(String),
+    // The `#` in a plural sub-message.
+    Pound,
+    Arg { name: String, kind: ArgKind },
+}
+
+#[derive(Debug)]
+enum ArgKind {
+    // An argument without a type, such as `{name}`.
+    None,
+    // An argument that is formatted by ICU on its own, such as `{when, date, full}`.
+    Simple {
+        format: UMessageFormat,
+        value: SimpleValue,
+    },
+    // A `plural` or `selectordinal` argument.  `selector` formats the index of the chosen
+    // sub-message, so that ICU applies its plural rules.
+    Plural {
+        selector: UMessageFormat,
+        offset: f64,
+        messages: Vec<Vec<Part>>,
+    },
+    // A `select` argument.
+    Select {
+        messages: Vec<(String, Vec<Part>)>,
+    },
+}
+
+// The type of the single variadic argument passed to ICU for a simple argument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SimpleValue {
+    Double,
+    Integer,
+    Date,
+}
+
+impl NamedMessageFormat {
+    /// Creates a new message formatter for `pattern` in the given locale.
+    ///
+    /// Returns a [common::Error::Parse] if `pattern` is not a valid message pattern, and an error
+    /// if the same argument is used with two different types.
+    ///
+    /// Implements `umsg_open`.
+    pub fn try_new(pattern: &str, locale: &uloc::ULoc) -> Result<Self, common::Error> {
+        // ICU checks the syntax of the whole pattern, so that the parser below may assume that
+        // it is well formed.
+        UMessageFormat::try_from(&ustring::UChar::try_from(pattern)?, locale)?;
+
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            locale,
+            arguments: BTreeMap::new(),
+        };
+        let parts = parser.message(Parent::None)?;
+        if parser.pos != parser.chars.len() {
+            return Err(parser.error("unmatched '}'"));
+        }
+        Ok(NamedMessageFormat {
+            parts,
+            arguments: parser.arguments,
+            number: open("{0,number}", locale)?,
+        })
+    }
+
+    /// Returns the names of all the arguments in the pattern, including the ones nested in
+    /// `plural` and `select` arguments, together with the types of the values they expect.
+    pub fn arguments(&self) -> &BTreeMap<String, ArgType> {
+        &self.arguments
+    }
+
+    /// Formats the message with the given arguments.
+    ///
+    /// Only the arguments that end up in the message need to be given.  For example, the
+    /// arguments in the sub-messages of a `plural` argument that are not chosen may be left out.
+    /// Returns an error if an argument is missing, or if its value is not of the type that the
+    /// pattern expects.  The value of an `integer` argument must be a whole number that fits
+    /// into 32 bits.
+    ///
+    /// Implements `umsg_vformat`.
+    pub fn format(&self, args: &HashMap<&str, MessageArg>) -> Result<String, common::Error> {
+        let mut result = String::new();
+        self.format_parts(&self.parts, args, None, &mut result)?;
+        Ok(result)
+    }
+
+    // Appends the formatted `parts` to `result`.  `pound` is the number that replaces `#`.
+    fn format_parts(
+        &self,
+        parts: &[Part],
+        args: &HashMap<&str, MessageArg>,
+        pound: Option<f64>,
+        result: &mut String,
+    ) -> Result<(), common::Error> {
+        for part in parts {
+            match part {
+                Part::Text(text) => result.push_str(text),
+                Part::Pound => {
+                    let number = pound.expect("programmer error: '#' outside of plural");
+                    result.push_str(&message_format!(self.number, { number => Double })?);
+                }
+                Part::Arg { name, kind } => {
+                    let arg = args.get(name.as_str()).ok_or_else(|| {
+                        common::Error::wrapper(anyhow!("missing message argument: {}", name))
+                    })?;
+                    self.format_arg(name, kind, arg, args, result)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn format_arg(
+        &self,
+        name: &str,
+        kind: &ArgKind,
+        arg: &MessageArg,
+        args: &HashMap<&str, MessageArg>,
+        result: &mut String,
+    ) -> Result<(), common::Error> {
+        match (kind, arg) {
+            (ArgKind::None, MessageArg::String(s)) => result.push_str(s),
+            (ArgKind::None, MessageArg::Number(number)) => {
+                let number = *number;
+                result.push_str(&message_format!(self.number, { number => Double })?);
+            }
+            (ArgKind::Simple { format, value }, MessageArg::Number(number))
+                if *value != SimpleValue::Date =>
+            {
+                let number = *number;
+                let formatted = if *value == SimpleValue::Integer {
+                    // ICU takes integer arguments as 32 bit values.
+                    if number.fract() != 0.0 || number < i32::MIN.into() || number > i32::MAX.into()
+                    {
+                        return Err(common::Error::wrapper(anyhow!(
+                            "message argument {} is not a 32 bit integer: {}",
+                            name,
+                            number
+                        )));
+                    }
+                    let number = number as i32;
+                    message_format!(format, { number => Integer })?
+                } else {
+                    message_format!(format, { number => Double })?
+                };
+                result.push_str(&formatted);
+            }
+            (ArgKind::Simple { format, value }, MessageArg::Date(date))
+                if *value == SimpleValue::Date =>
+            {
+                let date = *date;
+                result.push_str(&message_format!(format, { date => Date })?);
+            }
+            (
+                ArgKind::Plural {
+                    selector,
+                    offset,
+                    messages,
+                },
+                MessageArg::Number(number),
+            ) => {
+                let number = *number;
+                let index = message_format!(selector, { number => Double })?;
+                let index: usize = index.parse().map_err(common::Error::wrapper)?;
+                self.format_parts(&messages[index], args, Some(number - offset), result)?;
+            }
+            (ArgKind::Select { messages }, MessageArg::String(s)) => {
+                let message = messages
+                    .iter()
+                    .find(|(keyword, _)| keyword == s)
+                    .or_else(|| messages.iter().find(|(keyword, _)| keyword == "other"))
+                    .map(|(_, message)| message)
+                    .ok_or_else(|| {
+                        common::Error::wrapper(anyhow!("no 'other' in select argument: {}", name))
+                    })?;
+                self.format_parts(message, args, None, result)?;
+            }
+            _ => {
+                return Err(common::Error::wrapper(anyhow!(
+                    "message argument {} has the wrong type: {:?}",
+                    name,
+                    arg
+                )))
+            }
+        }
+        Ok(())
+    }
+}
+
+// Opens a formatter for a pattern with a single argument.
+fn open(pattern: &str, locale: &uloc::ULoc) -> Result<UMessageFormat, common::Error> {
+    UMessageFormat::try_from(&ustring::UChar::try_from(pattern)?, locale)
+}
+
+// The kind of argument that a message is a sub-message of.  It determines which characters are
+// special in the message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Parent {
+    None,
+    Plural,
+    Select,
+}
+
+// Parses message patterns, following the ICU default apostrophe mode, in which an apostrophe only
+// starts a quoted literal if it is followed by a syntax character.
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    locale: &'a uloc::ULoc,
+    arguments: BTreeMap<String, ArgType>,
+}
+
+impl<'a> Parser<'a> {
+    // Parses a (sub-)message up to, but not including, the '}' that ends it.
+    fn message(&mut self, parent: Parent) -> Result<Vec<Part>, common::Error> {
+        let mut parts = vec![];
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '\'' => self.apostrophe(parent, &mut text),
+                '#' if parent == Parent::Plural => {
+                    self.pos += 1;
+                    flush(&mut text, &mut parts);
+                    parts.push(Part::Pound);
+                }
+                '{' => {
+                    flush(&mut text, &mut parts);
+                    parts.push(self.argument()?);
+                }
+                '}' => break,
+                c => {
+                    self.pos += 1;
+                    text.push(c);
+                }
+            }
+        }
+        flush(&mut text, &mut parts);
+        Ok(parts)
+    }
+
+    // Reads an apostrophe, and the quoted literal it starts, if any, into `text`.
+    fn apostrophe(&mut self, parent: Parent, text: &mut String) {
+        self.pos += 1;
+        match self.peek() {
+            Some('\'') => {
+                self.pos += 1;
+                text.push('\'');
+            }
+            Some('{') | Some('}') => self.quoted(text),
+            Some('#') if parent == Parent::Plural => self.quoted(text),
+            _ => text.push('\''),
+        }
+    }
+
+    // Reads a quoted literal up to the closing apostrophe, or the end of the pattern.
+    fn quoted(&mut self, text: &mut String) {
+        while let Some(c) = self.next() {
+            if c != '\'' {
+                text.push(c);
+            } else if self.peek() == Some('\'') {
+                self.pos += 1;
+                text.push('\'');
+            } else {
+                break;
+            }
+        }
+    }
+
+    // Parses an argument, from the opening to the closing brace.
+    fn argument(&mut self) -> Result<Part, common::Error> {
+        self.expect('{')?;
+        let name = self.word();
+        if self.skip('}') {
+            self.record(&name, ArgType::Any)?;
+            return Ok(Part::Arg {
+                name,
+                kind: ArgKind::None,
+            });
+        }
+        self.expect(',')?;
+        let arg_type = self.word().to_lowercase();
+        let kind = match arg_type.as_str() {
+            "plural" | "selectordinal" => {
+                self.expect(',')?;
+                self.record(&name, ArgType::Number)?;
+                self.plural(&arg_type)?
+            }
+            "select" => {
+                self.expect(',')?;
+                self.record(&name, ArgType::String)?;
+                ArgKind::Select {
+                    messages: self.sub_messages(Parent::Select)?,
+                }
+            }
+            "number" | "spellout" | "ordinal" | "duration" | "choice" | "date" | "time" => {
+                let style = if self.skip('}') {
+                    None
+                } else {
+                    self.expect(',')?;
+                    Some(self.style()?)
+                };
+                let (value, expected) = match arg_type.as_str() {
+                    "date" | "time" => (SimpleValue::Date, ArgType::Date),
+                    "number"
+                        if style
+                            .as_ref()
+                            .map(|style| style.trim().eq_ignore_ascii_case("integer"))
+                            .unwrap_or(false) =>
+                    {
+                        (SimpleValue::Integer, ArgType::Number)
+                    }
+                    _ => (SimpleValue::Double, ArgType::Number),
+                };
+                self.record(&name, expected)?;
+                let pattern = match style {
+                    Some(style) => format!("{{0,{},{}}}", arg_type, style),
+                    None => format!("{{0,{}}}", arg_type),
+                };
+                return Ok(Part::Arg {
+                    name,
+                    kind: ArgKind::Simple {
+                        format: open(&pattern, self.locale)?,
+                        value,
+                    },
+                });
+            }
+            _ => return Err(self.error("unknown argument type")),
+        };
+        self.expect('}')?;
+        Ok(Part::Arg { name, kind })
+    }
+
+    // Parses the style of a `plural` or `selectordinal` argument, up to its closing brace.
+    fn plural(&mut self, arg_type: &str) -> Result<ArgKind, common::Error> {
+        self.skip_whitespace();
+        let mut offset = 0.0;
+        if self.chars[self.pos..].starts_with(&['o', 'f', 'f', 's', 'e', 't', ':']) {
+            self.pos += "offset:".len();
+            offset = self
+                .word()
+                .parse()
+                .map_err(|_| self.error("invalid plural offset"))?;
+        }
+        let sub_messages = self.sub_messages(Parent::Plural)?;
+
+        // The sub-messages of the selector are the indices of the real ones.
+        let mut selector = format!("{{0,{},offset:{}", arg_type, offset);
+        let mut messages = vec![];
+        for (index, (keyword, message)) in sub_messages.into_iter().enumerate() {
+            selector.push_str(&format!(" {}{{{}}}", keyword, index));
+            messages.push(message);
+        }
+        selector.push('}');
+        Ok(ArgKind::Plural {
+            selector: open(&selector, self.locale)?,
+            offset,
+            messages,
+        })
+    }
+
+    // Parses `keyword {message}` pairs, up to the closing brace of the argument.
+    fn sub_messages(&mut self, parent: Parent) -> Result<Vec<(String, Vec<Part>)>, common::Error> {
+        let mut messages = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('}') || self.peek().is_none() {
+                return Ok(messages);
+            }
+            let keyword = self.word();
+            self.expect('{')?;
+            let message = self.message(parent)?;
+            self.expect('}')?;
+            messages.push((keyword, message));
+        }
+    }
+
+    // Returns the style of a simple argument verbatim, and skips its closing brace.
+    fn style(&mut self) -> Result<String, common::Error> {
+        let start = self.pos;
+        let mut nesting = 0;
+        while let Some(c) = self.next() {
+            match c {
+                '\'' => {
+                    while let Some(c) = self.next() {
+                        if c == '\'' {
+                            break;
+                        }
+                    }
+                }
+                '{' => nesting += 1,
+                '}' if nesting == 0 => {
+                    return Ok(self.chars[start..self.pos - 1].iter().collect());
+                }
+                '}' => nesting -= 1,
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated argument"))
+    }
+
+    // Returns the next run of characters that are neither white space nor syntax characters,
+    // skipping white space around it.
+    fn word(&mut self) -> String {
+        self.skip_whitespace();
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == ',' || c == '{' || c == '}' {
+                break;
+            }
+            self.pos += 1;
+            word.push(c);
+        }
+        self.skip_whitespace();
+        word
+    }
+
+    // Notes that the argument `name` is used with the type `arg_type`.
+    fn record(&mut self, name: &str, arg_type: ArgType) -> Result<(), common::Error> {
+        let known = self
+            .arguments
+            .entry(name.to_string())
+            .or_insert(ArgType::Any);
+        if *known == ArgType::Any {
+            *known = arg_type;
+        } else if arg_type != ArgType::Any && arg_type != *known {
+            return Err(self.error("argument used with different types"));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map(char::is_whitespace).unwrap_or(false) {
+            self.pos += 1;
+        }
+    }
+
+    // Skips `expected` if it is next, after white space.
+    fn skip(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return false;
+        }
+        self.pos += 1;
+        true
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), common::Error> {
+        if self.skip(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    fn error(&self, message: &str) -> common::Error {
+        common::Error::wrapper(anyhow!(
+            "{} at offset {} of message pattern",
+            message,
+            self.pos
+        ))
+    }
+}
+
+// Moves the text collected so far into `parts`.
+fn flush(text: &mut String, parts: &mut Vec<Part>) {
+    if !text.is_empty() {
+        parts.push(Part::Text(std::mem::take(text)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args<'a>(args: &[(&'a str, MessageArg)]) -> HashMap<&'a str, MessageArg> {
+        args.iter().cloned().collect()
+    }
+
+    #[test]
+    fn simple_arguments() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("en-US")?;
+        let fmt = NamedMessageFormat::try_new(
+            "{name} paid {amount, number, integer} on {when, date, ::yMMMd} \
+             ('{'not an argument'}', it''s {rate,number,##.#}%, {rate}).",
+            &loc,
+        )?;
+        let result = fmt.format(&args(&[
+            ("name", "Ana".into()),
+            ("amount", 31337.0.into()),
+            // 2020-03-02T12:00:00Z
+            ("when", MessageArg::Date(1583150400000.0)),
+            ("rate", 43.43.into()),
+        ]))?;
+        assert_eq!(
+            "Ana paid 31,337 on Mar 2, 2020 ({not an argument}, it's 43.4%, 43.43).",
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nested_plural_and_select() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("en-US")?;
+        let fmt = NamedMessageFormat::try_new(
+            "{gender, select, \
+               female {{count, plural, offset:1 \
+                 =0 {{host} does not give a party.} \
+                 =1 {{host} invites {guest} to her party.} \
+                 other {{host} invites {guest} and # other '#'{count, selectordinal, \
+                   one {#st} two {#nd} few {#rd} other {#th}} people to her party.}}} \
+               other {{count, plural, offset:1 \
+                 =0 {{host} does not give a party.} \
+                 =1 {{host} invites {guest} to their party.} \
+                 other {{host} invites {guest} and # other people to their party.}}}}",
+            &loc,
+        )?;
+        assert_eq!(
+            "Ana invites Bo and 1 other #2nd people to her party.",
+            fmt.format(&args(&[
+                ("gender", "female".into()),
+                ("count", 2.into()),
+                ("host", "Ana".into()),
+                ("guest", "Bo".into()),
+            ]))?
+        );
+        assert_eq!(
+            "Ana invites Bo and 1,233 other people to their party.",
+            fmt.format(&args(&[
+                ("gender", "other".into()),
+                ("count", 1234.into()),
+                ("host", "Ana".into()),
+                ("guest", "Bo".into()),
+            ]))?
+        );
+        // The guest is only needed if there is one.
+        assert_eq!(
+            "Ana does not give a party.",
+            fmt.format(&args(&[
+                ("gender", "unknown".into()),
+                ("count", 0.into()),
+                ("host", "Ana".into()),
+            ]))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn arguments_and_types() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("en-US")?;
+        let fmt = NamedMessageFormat::try_new(
+            "{a} {b, number} {c, date} {d, select, x {{e} {f, time, short}} other {{a}}} \
+             {g, plural, other {{h, spellout} #}}",
+            &loc,
+        )?;
+        let expected: BTreeMap<String, ArgType> = vec![
+            ("a", ArgType::Any),
+            ("b", ArgType::Number),
+            ("c", ArgType::Date),
+            ("d", ArgType::String),
+            ("e", ArgType::Any),
+            ("f", ArgType::Date),
+            ("g", ArgType::Number),
+            ("h", ArgType::Number),
+        ]
+        .into_iter()
+        .map(|(name, arg_type)| (name.to_string(), arg_type))
+        .collect();
+        assert_eq!(&expected, fmt.arguments());
+        Ok(())
+    }
+
+    #[test]
+    fn errors() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("en-US")?;
+        match NamedMessageFormat::try_new("{count, plural, one {x}", &loc) {
+            Err(common::Error::Parse { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(NamedMessageFormat::try_new("{a, number} {a, date}", &loc).is_err());
+
+        let fmt = NamedMessageFormat::try_new("{count, plural, other {# {what}}}", &loc)?;
+        assert!(fmt.format(&args(&[("count", 2.into())])).is_err());
+        assert!(fmt
+            .format(&args(&[("count", "two".into()), ("what", "x".into())]))
+            .is_err());
+        assert_eq!(
+            "2 x",
+            fmt.format(&args(&[("count", 2.into()), ("what", "x".into())]))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn integer_arguments() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("en-US")?;
+        let fmt = NamedMessageFormat::try_new("{n, number, integer}", &loc)?;
+        assert_eq!("-42", fmt.format(&args(&[("n", (-42.0).into())]))?);
+        assert_eq!(
+            "2,147,483,647",
+            fmt.format(&args(&[("n", f64::from(i32::MAX).into())]))?
+        );
+        assert_eq!(
+            "-2,147,483,648",
+            fmt.format(&args(&[("n", f64::from(i32::MIN).into())]))?
+        );
+        for n in &[
+            0.5,
+            -2.5,
+            f64::from(i32::MAX) + 1.0,
+            f64::from(i32::MIN) - 1.0,
+            f64::NAN,
+            f64::INFINITY,
+        ] {
+            assert!(
+                fmt.format(&args(&[("n", (*n).into())])).is_err(),
+                "n: {}",
+                n
+            );
+        }
+        Ok(())
+    }
+}
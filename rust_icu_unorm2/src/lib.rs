@@ -0,0 +1,367 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU normalization support for rust
+//!
+//! This crate provides [Unicode normalization](https://unicode.org/reports/tr15/), as
+//! implemented by the ICU library.  Specifically the functionality exposed through its C API, as
+//! available in the [header
+//! `unorm2.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/unorm2_8h.html).
+//!
+//! The main type is [UNormalizer], which is obtained for one of the standard normalization forms
+//! through [UNormalizer::new_nfc], [UNormalizer::new_nfd], [UNormalizer::new_nfkc] and
+//! [UNormalizer::new_nfkd].  A normalizer can be restricted to a subset of characters through
+//! [UNormalizer::filtered].
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_icu_unorm2 as unorm2;
+//! let nfc = unorm2::UNormalizer::new_nfc().expect("nfc");
+//! let nfd = unorm2::UNormalizer::new_nfd().expect("nfd");
+//! // "é" as a single code point, and as "e" followed by a combining acute accent.
+//! assert_eq!("\u{e9}", nfc.normalize("e\u{301}").expect("normalize"));
+//! assert_eq!("e\u{301}", nfd.normalize("\u{e9}").expect("normalize"));
+//! assert!(!nfc.is_normalized("e\u{301}").expect("is_normalized"));
+//! ```
+
+use {
+    rust_icu_common as common,
+    rust_icu_common::buffered_uchar_method_with_retry,
+    rust_icu_sys as sys,
+    rust_icu_sys::versioned_function,
+    rust_icu_sys::*,
+    rust_icu_ustring as ustring,
+    std::{convert::TryFrom, ptr},
+};
+
+// A set of characters, used to restrict the characters that a filtered normalizer affects.
+//
+// Implements `USet`.
+#[derive(Debug)]
+struct FilterSet {
+    rep: ptr::NonNull<sys::USet>,
+}
+
+impl Drop for FilterSet {
+    // Implements `uset_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(uset_close)(self.rep.as_ptr()) };
+    }
+}
+
+impl FilterSet {
+    // Creates a set from a pattern such as `[^äöü]`.
+    //
+    // Implements `uset_openPattern` and `uset_freeze`
+    fn try_from_pattern(pattern: &str) -> Result<FilterSet, common::Error> {
+        let pattern = ustring::UChar::try_from(pattern)?;
+        let mut status = common::Error::OK_CODE;
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uset_openPattern)(
+                pattern.as_c_ptr(),
+                pattern.len() as i32,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("uset_openPattern", status)?;
+        let rep = ptr::NonNull::new(rep).unwrap();
+        // A frozen set is immutable, which is what a filtered normalizer expects.
+        unsafe { versioned_function!(uset_freeze)(rep.as_ptr()) };
+        Ok(FilterSet { rep })
+    }
+}
+
+/// A Unicode normalizer.
+///
+/// Implements `UNormalizer2` from ICU.
+#[derive(Debug)]
+pub struct UNormalizer {
+    rep: *const sys::UNormalizer2,
+    // Filtered normalizers are owned by this object, and refer to both the filter set and the
+    // normalizer that they filter, which therefore must live at least as long.  The standard
+    // normalizers are owned by ICU, and have neither.
+    filter: Option<FilterSet>,
+    _base: Option<Box<UNormalizer>>,
+}
+
+impl Drop for UNormalizer {
+    /// Implements `unorm2_close`
+    fn drop(&mut self) {
+        if self.filter.is_some() {
+            unsafe { versioned_function!(unorm2_close)(self.rep as *mut sys::UNormalizer2) };
+        }
+    }
+}
+
+/// Generates a constructor for one of the standard normalizer instances.
+macro_rules! standard_instance {
+    ($method_name:ident, $function_name:ident, $doc:literal, $implements:literal) => {
+        #[doc = $doc]
+        ///
+        /// The returned normalizer is shared and owned by ICU, so this is inexpensive.
+        ///
+        #[doc = $implements]
+        pub fn $method_name() -> Result<UNormalizer, common::Error> {
+            let mut status = common::Error::OK_CODE;
+            let rep = unsafe {
+                assert!(common::Error::is_ok(status));
+                versioned_function!($function_name)(&mut status)
+            };
+            common::Error::ok_or_warning(stringify!($function_name), status)?;
+            assert!(!rep.is_null());
+            Ok(UNormalizer {
+                rep,
+                filter: None,
+                _base: None,
+            })
+        }
+    };
+}
+
+impl UNormalizer {
+    standard_instance!(
+        new_nfc,
+        unorm2_getNFCInstance,
+        "Returns a normalizer for Normalization Form C (canonical composition).",
+        "Implements `unorm2_getNFCInstance`"
+    );
+    standard_instance!(
+        new_nfd,
+        unorm2_getNFDInstance,
+        "Returns a normalizer for Normalization Form D (canonical decomposition).",
+        "Implements `unorm2_getNFDInstance`"
+    );
+    standard_instance!(
+        new_nfkc,
+        unorm2_getNFKCInstance,
+        "Returns a normalizer for Normalization Form KC (compatibility composition).",
+        "Implements `unorm2_getNFKCInstance`"
+    );
+    standard_instance!(
+        new_nfkd,
+        unorm2_getNFKDInstance,
+        "Returns a normalizer for Normalization Form KD (compatibility decomposition).",
+        "Implements `unorm2_getNFKDInstance`"
+    );
+    standard_instance!(
+        new_nfkc_casefold,
+        unorm2_getNFKCCasefoldInstance,
+        "Returns a normalizer for NFKC followed by case folding, also known as NFKC_Casefold.",
+        "Implements `unorm2_getNFKCCasefoldInstance`"
+    );
+
+    /// Consumes this normalizer, and returns one that only normalizes the characters in the set
+    /// given by `pattern`, and passes all other characters through unchanged.
+    ///
+    /// `pattern` is a set in the ICU [UnicodeSet
+    /// syntax](http://userguide.icu-project.org/strings/unicodeset), for example `[^ä]`
+    /// or `[:Latin:]`.  Filtered normalizers can be filtered further, in which case only the
+    /// characters in all of the sets are normalized.
+    ///
+    /// Implements `unorm2_openFiltered`
+    pub fn filtered(self, pattern: &str) -> Result<UNormalizer, common::Error> {
+        let filter = FilterSet::try_from_pattern(pattern)?;
+        let mut status = common::Error::OK_CODE;
+        // Requires that self.rep and filter outlive the returned normalizer, which is ensured by
+        // moving both into it.
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(unorm2_openFiltered)(self.rep, filter.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("unorm2_openFiltered", status)?;
+        assert!(!rep.is_null());
+        Ok(UNormalizer {
+            rep,
+            filter: Some(filter),
+            _base: Some(Box::new(self)),
+        })
+    }
+
+    /// Normalizes `text`.
+    ///
+    /// Implements `unorm2_normalize`
+    pub fn normalize(&self, text: &str) -> Result<String, common::Error> {
+        let text = ustring::UChar::try_from(text)?;
+        let result = self.normalize_uchar(&text)?;
+        String::try_from(&result)
+    }
+
+    /// Normalizes `text`, given as a [ustring::UChar].
+    ///
+    /// Implements `unorm2_normalize`
+    pub fn normalize_uchar(&self, text: &ustring::UChar) -> Result<ustring::UChar, common::Error> {
+        const CAPACITY: usize = 1024;
+        buffered_uchar_method_with_retry!(
+            normalize_impl,
+            CAPACITY,
+            [rep: *const sys::UNormalizer2, src: *const sys::UChar, length: i32,],
+            []
+        );
+        normalize_impl(
+            "unorm2_normalize",
+            versioned_function!(unorm2_normalize),
+            self.rep,
+            text.as_c_ptr(),
+            text.len() as i32,
+        )
+    }
+
+    /// Returns true if `text` is already normalized.
+    ///
+    /// Implements `unorm2_isNormalized`
+    pub fn is_normalized(&self, text: &str) -> Result<bool, common::Error> {
+        let text = ustring::UChar::try_from(text)?;
+        self.is_normalized_uchar(&text)
+    }
+
+    /// Same as [UNormalizer::is_normalized], but takes a [ustring::UChar].
+    ///
+    /// Implements `unorm2_isNormalized`
+    pub fn is_normalized_uchar(&self, text: &ustring::UChar) -> Result<bool, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let result = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(unorm2_isNormalized)(
+                self.rep,
+                text.as_c_ptr(),
+                text.len() as i32,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("unorm2_isNormalized", status)?;
+        Ok(result != 0)
+    }
+
+    /// Checks quickly whether `text` is normalized.  The answer may be `UNORM_MAYBE` for some
+    /// composed forms, in which case [UNormalizer::is_normalized] gives a definite answer.
+    ///
+    /// Implements `unorm2_quickCheck`
+    pub fn quick_check(&self, text: &str) -> Result<sys::UNormalizationCheckResult, common::Error> {
+        let text = ustring::UChar::try_from(text)?;
+        self.quick_check_uchar(&text)
+    }
+
+    /// Same as [UNormalizer::quick_check], but takes a [ustring::UChar].
+    ///
+    /// Implements `unorm2_quickCheck`
+    pub fn quick_check_uchar(
+        &self,
+        text: &ustring::UChar,
+    ) -> Result<sys::UNormalizationCheckResult, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let result = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(unorm2_quickCheck)(
+                self.rep,
+                text.as_c_ptr(),
+                text.len() as i32,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("unorm2_quickCheck", status)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_forms() -> Result<(), common::Error> {
+        // "ﬁ" ligature, followed by "Å" as "A" and a combining ring.
+        let text = "\u{fb01}A\u{30a}";
+        assert_eq!("\u{fb01}\u{c5}", UNormalizer::new_nfc()?.normalize(text)?);
+        assert_eq!("\u{fb01}A\u{30a}", UNormalizer::new_nfd()?.normalize(text)?);
+        assert_eq!("fi\u{c5}", UNormalizer::new_nfkc()?.normalize(text)?);
+        assert_eq!("fiA\u{30a}", UNormalizer::new_nfkd()?.normalize(text)?);
+        assert_eq!(
+            "fi\u{e5}",
+            UNormalizer::new_nfkc_casefold()?.normalize(text)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn uchar() -> Result<(), common::Error> {
+        let nfc = UNormalizer::new_nfc()?;
+        let text = ustring::UChar::try_from("A\u{30a}")?;
+        let result = nfc.normalize_uchar(&text)?;
+        assert_eq!(1, result.len());
+        assert!(nfc.is_normalized_uchar(&result)?);
+        assert_eq!(
+            sys::UNormalizationCheckResult::UNORM_YES,
+            nfc.quick_check_uchar(&result)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn quick_check() -> Result<(), common::Error> {
+        let nfc = UNormalizer::new_nfc()?;
+        assert_eq!(
+            sys::UNormalizationCheckResult::UNORM_YES,
+            nfc.quick_check("abc")?
+        );
+        assert_eq!(
+            sys::UNormalizationCheckResult::UNORM_MAYBE,
+            nfc.quick_check("A\u{30a}")?
+        );
+        assert!(!nfc.is_normalized("A\u{30a}")?);
+        let nfd = UNormalizer::new_nfd()?;
+        assert_eq!(
+            sys::UNormalizationCheckResult::UNORM_NO,
+            nfd.quick_check("\u{c5}")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn long_text() -> Result<(), common::Error> {
+        // Decomposition triples the length, which exceeds the initial buffer size.
+        let text = "\u{1e69}".repeat(500);
+        let result = UNormalizer::new_nfd()?.normalize(&text)?;
+        assert_eq!("s\u{323}\u{307}".repeat(500), result);
+        Ok(())
+    }
+
+    #[test]
+    fn filtered() -> Result<(), common::Error> {
+        // Decomposes everything except the German umlauts.
+        let nfd = UNormalizer::new_nfd()?.filtered("[^\u{e4}\u{f6}\u{fc}]")?;
+        assert_eq!("\u{fc}be\u{301}r", nfd.normalize("\u{fc}b\u{e9}r")?);
+        assert!(nfd.is_normalized("\u{fc}")?);
+
+        // Filters compose, so this leaves both umlauts and "é" alone.
+        let nfd = nfd.filtered("[^\u{e9}]")?;
+        assert_eq!(
+            "\u{fc}b\u{e9}ra\u{300}",
+            nfd.normalize("\u{fc}b\u{e9}r\u{e0}")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn bad_filter() -> Result<(), common::Error> {
+        let result = UNormalizer::new_nfc()?.filtered("[not a set");
+        assert!(result.is_err(), "was: {:?}", result);
+        Ok(())
+    }
+}
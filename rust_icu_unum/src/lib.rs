@@ -0,0 +1,294 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU number formatting support for rust
+//!
+//! This crate provides locale-sensitive number formatting, as implemented by the ICU library.
+//! Specifically the functionality exposed through its C API, as available in the [header
+//! `unum.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/unum_8h.html).
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+//!
+//! ## Examples
+//!
+//! Formatting a currency amount, then styling its parts separately.
+//!
+//! ```
+//! use rust_icu_sys as sys;
+//! use rust_icu_uloc as uloc;
+//! use rust_icu_unum as unum;
+//! use std::convert::TryFrom;
+//!
+//! let loc = uloc::ULoc::try_from("en-US-u-cu-eur").expect("locale");
+//! let fmt = unum::UNumberFormat::try_new_with_style(sys::UNumberFormatStyle::UNUM_CURRENCY, &loc)
+//!     .expect("formatter");
+//! let parts = fmt.format_f64_to_parts(1234.5).expect("formatted");
+//! assert_eq!("€1,234.50", parts.text());
+//! let currency = parts
+//!     .iter()
+//!     .find(|(field, _)| **field == sys::UNumberFormatFields::UNUM_CURRENCY_FIELD)
+//!     .map(|(_, text)| text);
+//! assert_eq!(Some("€"), currency);
+//! ```
+
+use {
+    rust_icu_common as common, rust_icu_sys as sys, rust_icu_sys::versioned_function,
+    rust_icu_sys::*, rust_icu_ufieldpositer as ufieldpositer, rust_icu_uloc as uloc,
+    rust_icu_ustring as ustring, std::convert::TryFrom, std::ptr,
+};
+
+/// The initial size of the buffers for formatted numbers.
+const CAPACITY: usize = 200;
+
+/// Formats numbers according to the conventions of a locale.
+///
+/// A number format may be used from any thread, but not from several threads at once, so it is
+/// `Send` but not `Sync`.  Use [UNumberFormat::try_clone] to give each thread its own copy.
+///
+/// Implements `UNumberFormat` from ICU.
+#[derive(Debug)]
+pub struct UNumberFormat {
+    rep: ptr::NonNull<sys::UNumberFormat>,
+}
+
+impl Drop for UNumberFormat {
+    /// Implements `unum_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(unum_close)(self.rep.as_ptr()) };
+    }
+}
+
+unsafe impl Send for UNumberFormat {}
+
+impl Clone for UNumberFormat {
+    /// Makes an independent copy of this number format.
+    ///
+    /// Panics if ICU can not allocate the copy.  Use [UNumberFormat::try_clone] to handle that
+    /// case.
+    fn clone(&self) -> Self {
+        self.try_clone().expect("unum_clone")
+    }
+}
+
+impl UNumberFormat {
+    /// Creates a new number format with one of the predefined styles, such as
+    /// `UNUM_DECIMAL` or `UNUM_CURRENCY`.
+    ///
+    /// The pattern styles can not be used here, use
+    /// [try_new_decimal_pattern](UNumberFormat::try_new_decimal_pattern) instead.
+    ///
+    /// Implements `unum_open`
+    pub fn try_new_with_style(
+        style: sys::UNumberFormatStyle,
+        locale: &uloc::ULoc,
+    ) -> Result<UNumberFormat, common::Error> {
+        assert_ne!(
+            style,
+            sys::UNumberFormatStyle::UNUM_PATTERN_DECIMAL,
+            "programmer error: use try_new_decimal_pattern instead"
+        );
+        assert_ne!(
+            style,
+            sys::UNumberFormatStyle::UNUM_PATTERN_RULEBASED,
+            "programmer error: rule based patterns are not supported"
+        );
+        let pattern = ustring::UChar::try_from("")?;
+        UNumberFormat::new_internal(style, &pattern, locale)
+    }
+
+    /// Creates a new number format from a decimal pattern, such as `#,##0.00`.
+    ///
+    /// Implements `unum_open`
+    pub fn try_new_decimal_pattern(
+        pattern: &str,
+        locale: &uloc::ULoc,
+    ) -> Result<UNumberFormat, common::Error> {
+        let pattern = ustring::UChar::try_from(pattern)?;
+        UNumberFormat::new_internal(
+            sys::UNumberFormatStyle::UNUM_PATTERN_DECIMAL,
+            &pattern,
+            locale,
+        )
+    }
+
+    // Implements `unum_open`
+    fn new_internal(
+        style: sys::UNumberFormatStyle,
+        pattern: &ustring::UChar,
+        locale: &uloc::ULoc,
+    ) -> Result<UNumberFormat, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let mut parse_error = sys::UParseError::default();
+        let asciiz_locale = locale.as_c_str();
+        // Requires that pattern and asciiz_locale are valid, which they are by construction.
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(unum_open)(
+                style,
+                pattern.as_c_ptr(),
+                pattern.len() as i32,
+                asciiz_locale.as_ptr(),
+                &mut parse_error,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_parse_error("unum_open", status, &parse_error)?;
+        Ok(UNumberFormat {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Makes an independent copy of this number format.
+    ///
+    /// Implements `unum_clone`
+    pub fn try_clone(&self) -> Result<UNumberFormat, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(unum_clone)(self.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("unum_clone", status)?;
+        Ok(UNumberFormat {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Formats an integer.
+    ///
+    /// Implements `unum_formatInt64`
+    pub fn format_i64(&self, number: i64) -> Result<String, common::Error> {
+        common::buffered_uchar_method_with_retry!(
+            format_int64,
+            CAPACITY,
+            [format: *const sys::UNumberFormat, number: i64,],
+            [pos: *mut sys::UFieldPosition,]
+        );
+        let result = format_int64(
+            "unum_formatInt64",
+            versioned_function!(unum_formatInt64),
+            self.rep.as_ptr(),
+            number,
+            ptr::null_mut(),
+        )?;
+        String::try_from(&result)
+    }
+
+    /// Formats a floating point number.
+    ///
+    /// Implements `unum_formatDouble`
+    pub fn format_f64(&self, number: f64) -> Result<String, common::Error> {
+        common::buffered_uchar_method_with_retry!(
+            format_double,
+            CAPACITY,
+            [format: *const sys::UNumberFormat, number: f64,],
+            [pos: *mut sys::UFieldPosition,]
+        );
+        let result = format_double(
+            "unum_formatDouble",
+            versioned_function!(unum_formatDouble),
+            self.rep.as_ptr(),
+            number,
+            ptr::null_mut(),
+        )?;
+        String::try_from(&result)
+    }
+
+    /// Formats a floating point number, and reports the parts that the formatted text is made
+    /// of, such as the integer part, the decimal separator and the currency symbol.  This is
+    /// the equivalent of `formatToParts` from ECMA 402.
+    ///
+    /// Implements `unum_formatDoubleForFields`
+    pub fn format_f64_to_parts(
+        &self,
+        number: f64,
+    ) -> Result<ufieldpositer::FormattedParts<sys::UNumberFormatFields>, common::Error> {
+        common::buffered_uchar_method_with_retry!(
+            format_double_for_fields,
+            CAPACITY,
+            [format: *const sys::UNumberFormat, number: f64,],
+            [positions: *mut sys::UFieldPositionIterator,]
+        );
+        let mut positions = ufieldpositer::UFieldPositionIterator::try_new()?;
+        let result = format_double_for_fields(
+            "unum_formatDoubleForFields",
+            versioned_function!(unum_formatDoubleForFields),
+            self.rep.as_ptr(),
+            number,
+            positions.as_mut_ptr(),
+        )?;
+        ufieldpositer::FormattedParts::try_new(
+            &result,
+            positions,
+            ufieldpositer::number_format_field,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, sys::UNumberFormatFields::*};
+
+    #[test]
+    fn format_decimal() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("de-DE")?;
+        let fmt = UNumberFormat::try_new_with_style(UNumberFormatStyle::UNUM_DECIMAL, &loc)?;
+        assert_eq!("1.234.567", fmt.format_i64(1234567)?);
+        assert_eq!("-1.234,5", fmt.format_f64(-1234.5)?);
+        Ok(())
+    }
+
+    #[test]
+    fn format_pattern() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("en-US")?;
+        let fmt = UNumberFormat::try_new_decimal_pattern("#,##0.000", &loc)?;
+        assert_eq!("1,234.500", fmt.format_f64(1234.5)?);
+        Ok(())
+    }
+
+    #[test]
+    fn format_to_parts() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("fr-FR")?;
+        let fmt = UNumberFormat::try_new_with_style(UNumberFormatStyle::UNUM_PERCENT, &loc)?;
+        let parts = fmt.format_f64_to_parts(-12.345)?;
+        assert_eq!("-1\u{202f}234\u{a0}%", parts.text());
+        let parts: Vec<(sys::UNumberFormatFields, &str)> =
+            parts.iter().map(|(field, text)| (*field, text)).collect();
+        assert_eq!(
+            vec![
+                (UNUM_SIGN_FIELD, "-"),
+                (UNUM_GROUPING_SEPARATOR_FIELD, "\u{202f}"),
+                (UNUM_INTEGER_FIELD, "1\u{202f}234"),
+                (UNUM_PERCENT_FIELD, "%"),
+            ],
+            parts
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn clone_in_another_thread() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("de-DE")?;
+        let fmt = UNumberFormat::try_new_decimal_pattern("#,##0.00", &loc)?;
+
+        let copy = fmt.try_clone()?;
+        let formatted = std::thread::spawn(move || copy.format_f64(1234.5))
+            .join()
+            .expect("thread finished")?;
+        assert_eq!("1.234,50", formatted);
+        assert_eq!(formatted, fmt.format_f64(1234.5)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,444 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU number and measurement unit formatting support for rust
+//!
+//! This crate formats numbers, and measurements such as "5.3 km", "12 fl oz" or "100 km/h", as
+//! implemented by the ICU library.  Specifically the functionality exposed through its C API, as
+//! available in the [header
+//! `unumberformatter.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/unumberformatter_8h.html).
+//!
+//! The formatter is configured with a [number
+//! skeleton](https://unicode-org.github.io/icu/userguide/format_parse/numbers/skeletons.html),
+//! such as `measure-unit/length-kilometer unit-width-short` or `percent .00`.
+//!
+//! With ICU 67 and later, [UNumberFormatter::try_new_for_unit] formats measurements in simple
+//! and compound units, named by their unit identifiers such as `kilometer-per-hour`.  With ICU 68
+//! and later, `UNumberFormatter::try_new_for_usage` also converts the measurements into the
+//! units that are preferred in the region of the locale, for example from meters to miles for
+//! `en-US`.  ICU 68 is newer than the versions that this crate is tested with, so the unit
+//! preferences are not available with those.
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_icu_uloc as uloc;
+//! use rust_icu_unumberformatter as unumf;
+//! use std::convert::TryFrom;
+//!
+//! let loc = uloc::ULoc::try_from("en-US").expect("locale");
+//! let skeleton = "measure-unit/length-kilometer unit-width-full-name";
+//! let fmt = unumf::UNumberFormatter::try_new(skeleton, &loc).expect("formatter");
+//! assert_eq!("5.3 kilometers", fmt.format_f64(5.3).expect("formatted"));
+//! ```
+
+use {
+    rust_icu_common as common, rust_icu_sys as sys, rust_icu_sys::versioned_function,
+    rust_icu_sys::*, rust_icu_uloc as uloc, rust_icu_ustring as ustring, std::convert::TryFrom,
+    std::ffi, std::ptr,
+};
+
+/// The initial size of the buffers for formatted numbers.
+const CAPACITY: usize = 200;
+
+/// How measurement units are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnitWidth {
+    /// The shortest form, which may be ambiguous, for example "5.3km" in English.
+    Narrow,
+    /// The abbreviated form, for example "5.3 km" in English.
+    Short,
+    /// The unit is spelled out, for example "5.3 kilometers" in English.
+    FullName,
+}
+
+impl UnitWidth {
+    // Returns the skeleton stem for this width.
+    fn skeleton(self) -> &'static str {
+        match self {
+            UnitWidth::Narrow => "unit-width-narrow",
+            UnitWidth::Short => "unit-width-short",
+            UnitWidth::FullName => "unit-width-full-name",
+        }
+    }
+}
+
+/// Formats numbers and measurements according to a skeleton and the conventions of a locale.
+///
+/// A formatter is immutable, and ICU allows it to be used from several threads at once, so it is
+/// both `Send` and `Sync`.
+///
+/// Implements `UNumberFormatter` from ICU.
+#[derive(Debug)]
+pub struct UNumberFormatter {
+    rep: ptr::NonNull<sys::UNumberFormatter>,
+}
+
+impl Drop for UNumberFormatter {
+    /// Implements `unumf_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(unumf_close)(self.rep.as_ptr()) };
+    }
+}
+
+unsafe impl Send for UNumberFormatter {}
+
+unsafe impl Sync for UNumberFormatter {}
+
+impl UNumberFormatter {
+    /// Creates a new formatter for the number `skeleton` in the given locale.
+    ///
+    /// Returns a [common::Error::Parse] if the skeleton is not valid.  The position of the error
+    /// in the skeleton is only reported with ICU 64 and later.
+    ///
+    /// Implements `unumf_openForSkeletonAndLocaleWithError`, or
+    /// `unumf_openForSkeletonAndLocale` before ICU 64.
+    pub fn try_new(skeleton: &str, locale: &uloc::ULoc) -> Result<UNumberFormatter, common::Error> {
+        let skeleton = ustring::UChar::try_from(skeleton)?;
+        let asciiz_locale = locale.as_c_str();
+        let mut status = common::Error::OK_CODE;
+        #[cfg(features = "icu_version_64_plus")]
+        {
+            let mut parse_error = sys::UParseError::default();
+            // Requires that skeleton and asciiz_locale are valid, which they are by construction.
+            let rep = unsafe {
+                assert!(common::Error::is_ok(status));
+                versioned_function!(unumf_openForSkeletonAndLocaleWithError)(
+                    skeleton.as_c_ptr(),
+                    skeleton.len() as i32,
+                    asciiz_locale.as_ptr(),
+                    &mut parse_error,
+                    &mut status,
+                )
+            };
+            common::Error::ok_or_parse_error(
+                "unumf_openForSkeletonAndLocaleWithError",
+                status,
+                &parse_error,
+            )?;
+            Ok(UNumberFormatter {
+                rep: ptr::NonNull::new(rep).unwrap(),
+            })
+        }
+        #[cfg(not(features = "icu_version_64_plus"))]
+        {
+            // Requires that skeleton and asciiz_locale are valid, which they are by construction.
+            let rep = unsafe {
+                assert!(common::Error::is_ok(status));
+                versioned_function!(unumf_openForSkeletonAndLocale)(
+                    skeleton.as_c_ptr(),
+                    skeleton.len() as i32,
+                    asciiz_locale.as_ptr(),
+                    &mut status,
+                )
+            };
+            common::Error::ok_or_warning("unumf_openForSkeletonAndLocale", status)?;
+            Ok(UNumberFormatter {
+                rep: ptr::NonNull::new(rep).unwrap(),
+            })
+        }
+    }
+
+    /// Creates a new formatter for measurements in `unit`, shown with the given width.
+    ///
+    /// The unit is a [unit
+    /// identifier](https://unicode.org/reports/tr35/tr35-general.html#Unit_Identifiers), such
+    /// as `kilometer`, `fluid-ounce`, or compound units such as `kilometer-per-hour`.
+    ///
+    /// Implements `unumf_openForSkeletonAndLocaleWithError`
+    #[cfg(features = "icu_version_67_plus")]
+    pub fn try_new_for_unit(
+        unit: &str,
+        width: UnitWidth,
+        locale: &uloc::ULoc,
+    ) -> Result<UNumberFormatter, common::Error> {
+        let skeleton = format!("unit/{} {}", unit, width.skeleton());
+        UNumberFormatter::try_new(&skeleton, locale)
+    }
+
+    /// Creates a new formatter for measurements given in `unit`, which are converted to the
+    /// units preferred for `usage` in the region of `locale`.
+    ///
+    /// The usage is one of the [unit preference
+    /// categories](https://github.com/unicode-org/cldr/blob/master/common/supplemental/units.xml)
+    /// of CLDR for the quantity of the unit, such as `road` or `person-height` for lengths, or
+    /// `default`.  For example, with the usage `road`, 5000 meters are formatted as "3.1 mi" for
+    /// `en-US`, and as "5 km" for `de-DE`.  The `-u-ms-` keyword of the locale, such as in
+    /// `en-US-u-ms-metric`, overrides the preferences of the region.
+    ///
+    /// Only available with ICU 68 and later.
+    ///
+    /// Implements `unumf_openForSkeletonAndLocaleWithError`
+    #[cfg(features = "icu_version_68_plus")]
+    pub fn try_new_for_usage(
+        unit: &str,
+        usage: &str,
+        width: UnitWidth,
+        locale: &uloc::ULoc,
+    ) -> Result<UNumberFormatter, common::Error> {
+        let skeleton = format!("unit/{} usage/{} {}", unit, usage, width.skeleton());
+        UNumberFormatter::try_new(&skeleton, locale)
+    }
+
+    /// Formats an integer.
+    ///
+    /// Implements `unumf_formatInt`
+    pub fn format_i64(&self, number: i64) -> Result<String, common::Error> {
+        let result = FormattedNumber::try_new()?;
+        let mut status = common::Error::OK_CODE;
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(unumf_formatInt)(
+                self.rep.as_ptr(),
+                number,
+                result.rep.as_ptr(),
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("unumf_formatInt", status)?;
+        result.to_string()
+    }
+
+    /// Formats a floating point number.
+    ///
+    /// Implements `unumf_formatDouble`
+    pub fn format_f64(&self, number: f64) -> Result<String, common::Error> {
+        let result = FormattedNumber::try_new()?;
+        let mut status = common::Error::OK_CODE;
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(unumf_formatDouble)(
+                self.rep.as_ptr(),
+                number,
+                result.rep.as_ptr(),
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("unumf_formatDouble", status)?;
+        result.to_string()
+    }
+
+    /// Formats a number given as a decimal string, such as `"12345678901234567890.5"`, without
+    /// losing precision.
+    ///
+    /// Implements `unumf_formatDecimal`
+    pub fn format_decimal(&self, number: &str) -> Result<String, common::Error> {
+        let asciiz_number = ffi::CString::new(number)?;
+        let result = FormattedNumber::try_new()?;
+        let mut status = common::Error::OK_CODE;
+        // Requires that asciiz_number is valid, which it is by construction.
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(unumf_formatDecimal)(
+                self.rep.as_ptr(),
+                asciiz_number.as_ptr(),
+                number.len() as i32,
+                result.rep.as_ptr(),
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("unumf_formatDecimal", status)?;
+        result.to_string()
+    }
+}
+
+/// The result of formatting a number.
+///
+/// Implements `UFormattedNumber` from ICU.
+struct FormattedNumber {
+    rep: ptr::NonNull<sys::UFormattedNumber>,
+}
+
+impl Drop for FormattedNumber {
+    /// Implements `unumf_closeResult`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(unumf_closeResult)(self.rep.as_ptr()) };
+    }
+}
+
+impl FormattedNumber {
+    /// Implements `unumf_openResult`
+    fn try_new() -> Result<FormattedNumber, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(unumf_openResult)(&mut status)
+        };
+        common::Error::ok_or_warning("unumf_openResult", status)?;
+        Ok(FormattedNumber {
+            rep: ptr::NonNull::new(rep).unwrap(),
+        })
+    }
+
+    /// Implements `unumf_resultToString`
+    fn to_string(&self) -> Result<String, common::Error> {
+        common::buffered_uchar_method_with_retry!(
+            result_to_string,
+            CAPACITY,
+            [result: *const sys::UFormattedNumber,],
+            []
+        );
+        let result = result_to_string(
+            "unumf_resultToString",
+            versioned_function!(unumf_resultToString),
+            self.rep.as_ptr(),
+        )?;
+        String::try_from(&result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_skeleton() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("de-DE")?;
+        let fmt = UNumberFormatter::try_new("percent .00", &loc)?;
+        assert_eq!("12,35\u{a0}%", fmt.format_f64(12.346)?);
+        assert_eq!("12,00\u{a0}%", fmt.format_i64(12)?);
+
+        let fmt = UNumberFormatter::try_new("", &loc)?;
+        assert_eq!(
+            "12.345.678.901.234.567.890,5",
+            fmt.format_decimal("12345678901234567890.5")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn format_measure_unit() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("en-US")?;
+        let fmt = UNumberFormatter::try_new("measure-unit/length-kilometer", &loc)?;
+        assert_eq!("5.3 km", fmt.format_f64(5.3)?);
+        Ok(())
+    }
+
+    // ICU only reports the position of skeleton syntax errors since version 64.
+    #[cfg(features = "icu_version_64_plus")]
+    #[test]
+    fn invalid_skeleton() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("en-US")?;
+        match UNumberFormatter::try_new("measure-unit/no-such-unit", &loc) {
+            Err(common::Error::Parse { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[cfg(features = "icu_version_67_plus")]
+    #[test]
+    fn format_units() -> Result<(), common::Error> {
+        struct Test {
+            locale: &'static str,
+            unit: &'static str,
+            width: UnitWidth,
+            number: f64,
+            expected: &'static str,
+        }
+        let tests = vec![
+            Test {
+                locale: "en-US",
+                unit: "kilometer",
+                width: UnitWidth::Short,
+                number: 5.3,
+                expected: "5.3 km",
+            },
+            Test {
+                locale: "en-US",
+                unit: "kilometer",
+                width: UnitWidth::Narrow,
+                number: 5.3,
+                expected: "5.3km",
+            },
+            Test {
+                locale: "en-US",
+                unit: "fluid-ounce",
+                width: UnitWidth::Short,
+                number: 12.0,
+                expected: "12 fl oz",
+            },
+            Test {
+                locale: "de-DE",
+                unit: "kilometer-per-hour",
+                width: UnitWidth::Short,
+                number: 100.0,
+                expected: "100 km/h",
+            },
+            Test {
+                locale: "fr-FR",
+                unit: "kilometer",
+                width: UnitWidth::FullName,
+                number: 5.3,
+                expected: "5,3\u{a0}kilomètres",
+            },
+        ];
+        for test in tests {
+            let loc = uloc::ULoc::try_from(test.locale)?;
+            let fmt = UNumberFormatter::try_new_for_unit(test.unit, test.width, &loc)?;
+            assert_eq!(
+                test.expected,
+                fmt.format_f64(test.number)?,
+                "unit: {}",
+                test.unit
+            );
+        }
+        Ok(())
+    }
+
+    #[cfg(features = "icu_version_68_plus")]
+    #[test]
+    fn format_for_usage() -> Result<(), common::Error> {
+        let tests = vec![
+            ("en-US", "3.1 mi"),
+            ("en-GB", "3.1 mi"),
+            ("de-DE", "5 km"),
+            ("en-US-u-ms-metric", "5 km"),
+        ];
+        for (locale, expected) in tests {
+            let loc = uloc::ULoc::try_from(locale)?;
+            let fmt = UNumberFormatter::try_new_for_usage("meter", "road", UnitWidth::Short, &loc)?;
+            assert_eq!(expected, fmt.format_i64(5000)?, "locale: {}", locale);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn shared_between_threads() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("en-US")?;
+        let fmt = std::sync::Arc::new(UNumberFormatter::try_new(
+            "measure-unit/length-meter unit-width-full-name",
+            &loc,
+        )?);
+        let threads: Vec<_> = (1..=4)
+            .map(|n| {
+                let fmt = fmt.clone();
+                std::thread::spawn(move || fmt.format_i64(n))
+            })
+            .collect();
+        let formatted = threads
+            .into_iter()
+            .map(|t| t.join().expect("thread finished"))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            vec!["1 meter", "2 meters", "3 meters", "4 meters"],
+            formatted
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,668 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU regular expression support for rust
+//!
+//! This crate provides regular expressions, as implemented by the ICU library.  Specifically the
+//! functionality exposed through its C API, as available in the [header
+//! `uregex.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/uregex_8h.html).
+//!
+//! ICU regular expressions support Unicode properties (e.g. `\p{Script=Greek}`), set operations
+//! on character classes, and matching of extended grapheme clusters with `\X`.  See the [ICU user
+//! guide](http://userguide.icu-project.org/strings/regexp) for the complete syntax.
+//!
+//! The main type is [URegularExpression].  A regular expression keeps the state of the current
+//! match, so matching requires a mutable reference.  All the offsets reported by this crate are
+//! byte offsets into the matched rust string.
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+//!
+//! ## Examples
+//!
+//! ```
+//! use rust_icu_uregex as uregex;
+//! let mut re = uregex::URegularExpression::new(r"(?<year>\d{4})-(\d{2})", &[]).expect("regex");
+//! let text = "From 2019-04 to 2020-11.";
+//!
+//! let found: Vec<&str> = re
+//!     .find_iter(text)
+//!     .expect("find_iter")
+//!     .map(|m| m.expect("match").as_str())
+//!     .collect();
+//! assert_eq!(vec!["2019-04", "2020-11"], found);
+//!
+//! let captures = re.captures(text).expect("captures").expect("a match");
+//! assert_eq!("2019", captures.name("year").expect("name").expect("group").as_str());
+//! assert_eq!("04", captures.get(2).expect("group").as_str());
+//!
+//! assert_eq!("From 04/2019 to 11/2020.", re.replace_all(text, "$2/$1").expect("replace_all"));
+//! ```
+
+use {
+    rust_icu_common as common,
+    rust_icu_common::buffered_uchar_method_with_retry,
+    rust_icu_sys as sys,
+    rust_icu_sys::versioned_function,
+    rust_icu_sys::*,
+    rust_icu_ustring as ustring, rust_icu_utext as utext,
+    std::{convert::TryFrom, ops, os::raw, ptr},
+};
+
+/// A single match of a regular expression, or of one of its capture groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'t> {
+    text: &'t str,
+    start: usize,
+    end: usize,
+}
+
+impl<'t> Match<'t> {
+    /// Returns the byte offset of the start of the match in the text.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the byte offset of the end of the match in the text.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the byte range of the match in the text.
+    pub fn range(&self) -> ops::Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns the matched text.
+    pub fn as_str(&self) -> &'t str {
+        &self.text[self.range()]
+    }
+}
+
+/// The capture groups of a single match.  Group 0 is the entire match.
+#[derive(Debug)]
+pub struct Captures<'r, 't> {
+    regex: &'r URegularExpression,
+    text: &'t str,
+    groups: Vec<Option<ops::Range<usize>>>,
+}
+
+impl<'r, 't> Captures<'r, 't> {
+    /// Returns the match for the group numbered `index`, or `None` if the group did not take
+    /// part in the match, or does not exist.
+    pub fn get(&self, index: usize) -> Option<Match<'t>> {
+        match self.groups.get(index) {
+            Some(Some(range)) => Some(Match {
+                text: self.text,
+                start: range.start,
+                end: range.end,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the match for the group named `name`, or `None` if the group did not take part
+    /// in the match.  It is an error if there is no group with that name.
+    pub fn name(&self, name: &str) -> Result<Option<Match<'t>>, common::Error> {
+        let index = self.regex.group_number(name)?;
+        Ok(self.get(index))
+    }
+
+    /// Returns the number of groups, including group 0.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Always returns false, since there is at least the group 0.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+/// An iterator over the successive non-overlapping matches in a text.  Created by
+/// [URegularExpression::find_iter].
+#[derive(Debug)]
+pub struct Matches<'r, 't> {
+    regex: &'r mut URegularExpression,
+    text: &'t str,
+    done: bool,
+}
+
+impl<'r, 't> Iterator for Matches<'r, 't> {
+    type Item = Result<Match<'t>, common::Error>;
+
+    /// Implements `uregex_findNext`
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.regex.find_next() {
+            Ok(true) => Some(self.regex.group(0).map(|range| {
+                let range = range.expect("group 0 always takes part in a match");
+                Match {
+                    text: self.text,
+                    start: range.start,
+                    end: range.end,
+                }
+            })),
+            Ok(false) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// A compiled regular expression.
+///
+/// ICU keeps referring to the text being matched until other text is given, so every method that
+/// takes a text first copies it into the regular expression.  The copy reuses the memory of the
+/// previous one, but still costs time linear in the length of the text.
+///
+/// Implements `URegularExpression` from ICU.
+#[derive(Debug)]
+pub struct URegularExpression {
+    rep: ptr::NonNull<sys::URegularExpression>,
+    // The text being matched.  ICU refers to the text instead of copying it, so it is kept here
+    // for as long as `rep` refers to it.
+    text: String,
+    // The settings that are applied every time that the text is set, as setting the text resets
+    // the region.
+    region: Option<ops::Range<usize>>,
+    anchoring_bounds: bool,
+    transparent_bounds: bool,
+}
+
+impl Drop for URegularExpression {
+    /// Implements `uregex_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(uregex_close)(self.rep.as_ptr()) };
+    }
+}
+
+impl URegularExpression {
+    /// Compiles the regular expression `pattern`, modified by `flags`, for example
+    /// `sys::URegexpFlag::UREGEX_CASE_INSENSITIVE`.
+    ///
+    /// Implements `uregex_open`
+    pub fn new(
+        pattern: &str,
+        flags: &[sys::URegexpFlag],
+    ) -> Result<URegularExpression, common::Error> {
+        let pattern = ustring::UChar::try_from(pattern)?;
+        let flags = flags.iter().fold(0u32, |acc, flag| acc | *flag as u32);
+        let mut status = common::Error::OK_CODE;
+        let mut parse_error = sys::UParseError::default();
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uregex_open)(
+                pattern.as_c_ptr(),
+                pattern.len() as i32,
+                flags,
+                &mut parse_error,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_parse_error("uregex_open", status, &parse_error)?;
+        Ok(URegularExpression {
+            rep: ptr::NonNull::new(rep).unwrap(),
+            text: String::new(),
+            region: None,
+            anchoring_bounds: true,
+            transparent_bounds: false,
+        })
+    }
+
+    /// Returns the source pattern of this regular expression.
+    ///
+    /// Implements `uregex_pattern`
+    pub fn pattern(&self) -> Result<String, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let mut len: i32 = 0;
+        let pattern = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uregex_pattern)(self.rep.as_ptr(), &mut len, &mut status)
+        };
+        common::Error::ok_or_warning("uregex_pattern", status)?;
+        // Requires that pattern points to len UTF-16 code units owned by self.rep.
+        let pattern = unsafe { std::slice::from_raw_parts(pattern, len as usize) };
+        String::from_utf16(pattern).map_err(common::Error::wrapper)
+    }
+
+    /// Returns the number of capture groups in this regular expression, not counting group 0.
+    ///
+    /// Implements `uregex_groupCount`
+    pub fn group_count(&self) -> Result<usize, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let count = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uregex_groupCount)(self.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("uregex_groupCount", status)?;
+        Ok(count as usize)
+    }
+
+    /// Returns the number of the capture group named `name`.
+    ///
+    /// Implements `uregex_groupNumberFromCName`
+    pub fn group_number(&self, name: &str) -> Result<usize, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let number = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uregex_groupNumberFromCName)(
+                self.rep.as_ptr(),
+                name.as_ptr() as *const raw::c_char,
+                name.len() as i32,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("uregex_groupNumberFromCName", status)?;
+        Ok(number as usize)
+    }
+
+    /// Limits the matching to the byte range `region` of the text.  The region applies to all
+    /// subsequent matching, except for [URegularExpression::replace_all] and
+    /// [URegularExpression::replace_first], which always operate on the entire text.
+    pub fn set_region(&mut self, region: ops::Range<usize>) {
+        self.region = Some(region);
+    }
+
+    /// Removes a region set by [URegularExpression::set_region].
+    pub fn reset_region(&mut self) {
+        self.region = None;
+    }
+
+    /// Sets whether `^` and `$` match at the boundaries of the region, as opposed to only at the
+    /// boundaries of the text.  On by default.
+    ///
+    /// The setting applies from the next search on, when `uregex_useAnchoringBounds` is called
+    /// with it.
+    pub fn use_anchoring_bounds(&mut self, enabled: bool) {
+        self.anchoring_bounds = enabled;
+    }
+
+    /// Sets whether lookahead, lookbehind and word boundaries can see the text outside of the
+    /// region.  Off by default.
+    ///
+    /// The setting applies from the next search on, when `uregex_useTransparentBounds` is called
+    /// with it.
+    pub fn use_transparent_bounds(&mut self, enabled: bool) {
+        self.transparent_bounds = enabled;
+    }
+
+    /// Returns true if the regular expression matches the entire text (or region).
+    ///
+    /// Implements `uregex_matches64`
+    pub fn matches(&mut self, text: &str) -> Result<bool, common::Error> {
+        self.set_text(text)?;
+        let mut status = common::Error::OK_CODE;
+        // The start index of -1 selects the start of the region.
+        let result = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uregex_matches64)(self.rep.as_ptr(), -1, &mut status)
+        };
+        common::Error::ok_or_warning("uregex_matches64", status)?;
+        Ok(result != 0)
+    }
+
+    /// Returns true if the regular expression matches a prefix of the text (or region).
+    ///
+    /// Implements `uregex_lookingAt64`
+    pub fn looking_at(&mut self, text: &str) -> Result<bool, common::Error> {
+        self.set_text(text)?;
+        let mut status = common::Error::OK_CODE;
+        // The start index of -1 selects the start of the region.
+        let result = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uregex_lookingAt64)(self.rep.as_ptr(), -1, &mut status)
+        };
+        common::Error::ok_or_warning("uregex_lookingAt64", status)?;
+        Ok(result != 0)
+    }
+
+    /// Returns true if the regular expression matches anywhere in the text (or region).
+    ///
+    /// Implements `uregex_findNext`
+    pub fn is_match(&mut self, text: &str) -> Result<bool, common::Error> {
+        Ok(self.find(text)?.is_some())
+    }
+
+    /// Returns the first match in the text (or region), if any.
+    ///
+    /// Implements `uregex_findNext`
+    pub fn find<'t>(&mut self, text: &'t str) -> Result<Option<Match<'t>>, common::Error> {
+        self.find_iter(text)?.next().transpose()
+    }
+
+    /// Returns an iterator over all the non-overlapping matches in the text (or region).
+    ///
+    /// Implements `uregex_findNext`
+    pub fn find_iter<'r, 't>(
+        &'r mut self,
+        text: &'t str,
+    ) -> Result<Matches<'r, 't>, common::Error> {
+        self.set_text(text)?;
+        Ok(Matches {
+            regex: self,
+            text,
+            done: false,
+        })
+    }
+
+    /// Returns the capture groups of the first match in the text (or region), if any.
+    ///
+    /// Implements `uregex_findNext`, `uregex_start64` and `uregex_end64`
+    pub fn captures<'r, 't>(
+        &'r mut self,
+        text: &'t str,
+    ) -> Result<Option<Captures<'r, 't>>, common::Error> {
+        self.set_text(text)?;
+        if !self.find_next()? {
+            return Ok(None);
+        }
+        let groups = (0..=self.group_count()?)
+            .map(|index| self.group(index))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(Captures {
+            regex: self,
+            text,
+            groups,
+        }))
+    }
+
+    /// Replaces all the matches in `text` with `replacement`.
+    ///
+    /// In the replacement, `$n` and `${name}` are replaced by the contents of the respective
+    /// capture groups, and `\` escapes the following character.
+    ///
+    /// Implements `uregex_replaceAll`
+    pub fn replace_all(&mut self, text: &str, replacement: &str) -> Result<String, common::Error> {
+        self.replace(
+            "uregex_replaceAll",
+            versioned_function!(uregex_replaceAll),
+            text,
+            replacement,
+        )
+    }
+
+    /// Same as [URegularExpression::replace_all], but only replaces the first match.
+    ///
+    /// Implements `uregex_replaceFirst`
+    pub fn replace_first(
+        &mut self,
+        text: &str,
+        replacement: &str,
+    ) -> Result<String, common::Error> {
+        self.replace(
+            "uregex_replaceFirst",
+            versioned_function!(uregex_replaceFirst),
+            text,
+            replacement,
+        )
+    }
+
+    // Runs one of the replace functions, which have identical signatures.
+    fn replace(
+        &mut self,
+        function: &'static str,
+        method_to_call: unsafe extern "C" fn(
+            *mut sys::URegularExpression,
+            *const sys::UChar,
+            i32,
+            *mut sys::UChar,
+            i32,
+            *mut sys::UErrorCode,
+        ) -> i32,
+        text: &str,
+        replacement: &str,
+    ) -> Result<String, common::Error> {
+        const CAPACITY: usize = 1024;
+        buffered_uchar_method_with_retry!(
+            replace_impl,
+            CAPACITY,
+            [rep: *mut sys::URegularExpression, replacement: *const sys::UChar, length: i32,],
+            []
+        );
+        self.set_text(text)?;
+        let replacement = ustring::UChar::try_from(replacement)?;
+        let result = replace_impl(
+            function,
+            method_to_call,
+            self.rep.as_ptr(),
+            replacement.as_c_ptr(),
+            replacement.len() as i32,
+        )?;
+        String::try_from(&result)
+    }
+
+    // Sets the text to match, and applies the region settings to it.
+    //
+    // Implements `uregex_setUText`, `uregex_setRegion64`, `uregex_useAnchoringBounds` and
+    // `uregex_useTransparentBounds`.
+    fn set_text(&mut self, text: &str) -> Result<(), common::Error> {
+        self.text.clear();
+        self.text.push_str(text);
+        // ICU makes a shallow copy of the UText, which refers to the contents of self.text.  The
+        // UText itself is no longer needed once set.
+        let mut utext = utext::Text::try_from(&self.text[..])?;
+        let mut status = common::Error::OK_CODE;
+        unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uregex_setUText)(self.rep.as_ptr(), utext.repr(), &mut status);
+            common::Error::ok_or_warning("uregex_setUText", status)?;
+            if let Some(region) = &self.region {
+                versioned_function!(uregex_setRegion64)(
+                    self.rep.as_ptr(),
+                    region.start as i64,
+                    region.end as i64,
+                    &mut status,
+                );
+                common::Error::ok_or_warning("uregex_setRegion64", status)?;
+            }
+            versioned_function!(uregex_useAnchoringBounds)(
+                self.rep.as_ptr(),
+                self.anchoring_bounds as sys::UBool,
+                &mut status,
+            );
+            common::Error::ok_or_warning("uregex_useAnchoringBounds", status)?;
+            versioned_function!(uregex_useTransparentBounds)(
+                self.rep.as_ptr(),
+                self.transparent_bounds as sys::UBool,
+                &mut status,
+            );
+        }
+        common::Error::ok_or_warning("uregex_useTransparentBounds", status)
+    }
+
+    // Finds the next match in the current text.  Returns false if there are no more matches.
+    //
+    // Implements `uregex_findNext`
+    fn find_next(&mut self) -> Result<bool, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let found = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uregex_findNext)(self.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("uregex_findNext", status)?;
+        Ok(found != 0)
+    }
+
+    // Returns the byte range of the capture group `index` in the current match, or `None` if the
+    // group did not take part in the match.  The range is checked to be a valid range of the
+    // text, so that it can be used to slice it.
+    //
+    // Implements `uregex_start64` and `uregex_end64`
+    fn group(&self, index: usize) -> Result<Option<ops::Range<usize>>, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let start = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uregex_start64)(self.rep.as_ptr(), index as i32, &mut status)
+        };
+        common::Error::ok_or_warning("uregex_start64", status)?;
+        if start < 0 {
+            return Ok(None);
+        }
+        let end = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(uregex_end64)(self.rep.as_ptr(), index as i32, &mut status)
+        };
+        common::Error::ok_or_warning("uregex_end64", status)?;
+        let out_of_bounds = |function| common::Error::Sys {
+            function,
+            code: sys::UErrorCode::U_INDEX_OUTOFBOUNDS_ERROR,
+        };
+        let (start, end) = (start as usize, end as usize);
+        if !self.text.is_char_boundary(start) {
+            return Err(out_of_bounds("uregex_start64"));
+        }
+        if end < start || !self.text.is_char_boundary(end) {
+            return Err(out_of_bounds("uregex_end64"));
+        }
+        Ok(Some(start..end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_error() {
+        let result = URegularExpression::new("(unclosed", &[]);
+        assert!(result.is_err(), "was: {:?}", result);
+        assert!(matches!(
+            result,
+            Err(common::Error::Parse {
+                function: "uregex_open",
+                code: sys::UErrorCode::U_REGEX_MISMATCHED_PAREN,
+                offset: Some(9),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn pattern_and_groups() -> Result<(), common::Error> {
+        let re = URegularExpression::new(r"(a)(?<second>b)?", &[])?;
+        assert_eq!(r"(a)(?<second>b)?", re.pattern()?);
+        assert_eq!(2, re.group_count()?);
+        assert_eq!(2, re.group_number("second")?);
+        assert!(re.group_number("third").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn flags() -> Result<(), common::Error> {
+        let mut re = URegularExpression::new("straße", &[])?;
+        assert!(!re.is_match("STRASSE")?);
+        let mut re =
+            URegularExpression::new("straße", &[sys::URegexpFlag::UREGEX_CASE_INSENSITIVE])?;
+        assert!(re.is_match("STRASSE")?);
+        Ok(())
+    }
+
+    #[test]
+    fn unicode_offsets() -> Result<(), common::Error> {
+        let mut re = URegularExpression::new(r"\p{Script=Greek}+", &[])?;
+        let text = "❤ αβγ and δ";
+        let found = re
+            .find_iter(text)?
+            .collect::<Result<Vec<Match>, common::Error>>()?;
+        assert_eq!(2, found.len());
+        assert_eq!("αβγ", found[0].as_str());
+        assert_eq!(4..10, found[0].range());
+        assert_eq!("δ", found[1].as_str());
+        Ok(())
+    }
+
+    #[test]
+    fn graphemes() -> Result<(), common::Error> {
+        let mut re = URegularExpression::new(r"\X", &[])?;
+        let text = "e\u{301}🇨🇭";
+        let found: Vec<&str> = re
+            .find_iter(text)?
+            .map(|m| m.expect("match").as_str())
+            .collect();
+        assert_eq!(vec!["e\u{301}", "🇨🇭"], found);
+        Ok(())
+    }
+
+    #[test]
+    fn captures() -> Result<(), common::Error> {
+        let mut re = URegularExpression::new(r"(\w+)@(?<host>\w+)(\.com)?", &[])?;
+        assert!(re.captures("no address")?.is_none());
+
+        let captures = re.captures("write to joe@example")?.expect("a match");
+        assert_eq!(4, captures.len());
+        assert_eq!("joe@example", captures.get(0).unwrap().as_str());
+        assert_eq!("joe", captures.get(1).unwrap().as_str());
+        assert_eq!(9..12, captures.get(1).unwrap().range());
+        assert_eq!("example", captures.name("host")?.unwrap().as_str());
+        assert_eq!(None, captures.get(3));
+        assert_eq!(None, captures.get(4));
+        Ok(())
+    }
+
+    #[test]
+    fn replace() -> Result<(), common::Error> {
+        let mut re = URegularExpression::new(r"(\w+) (\w+)", &[])?;
+        assert_eq!("b a d c", re.replace_all("a b c d", "$2 $1")?);
+        assert_eq!("b a c d", re.replace_first("a b c d", "$2 $1")?);
+        assert_eq!("$ a $ c", re.replace_all("a b c d", r"\$ $1")?);
+
+        let mut re = URegularExpression::new(r"(?<word>\w+)", &[])?;
+        let long = "word ".repeat(500);
+        assert_eq!("<word> ".repeat(500), re.replace_all(&long, "<${word}>")?);
+        Ok(())
+    }
+
+    #[test]
+    fn region_and_anchoring() -> Result<(), common::Error> {
+        let mut re = URegularExpression::new(r"^\d+$", &[])?;
+        let text = "ab 123 cd";
+        assert!(!re.is_match(text)?);
+
+        re.set_region(3..6);
+        assert!(re.matches(text)?);
+        assert_eq!(Some(3..6), re.find(text)?.map(|m| m.range()));
+
+        re.use_anchoring_bounds(false);
+        assert!(!re.is_match(text)?);
+
+        re.reset_region();
+        re.use_anchoring_bounds(true);
+        assert!(!re.matches(text)?);
+
+        let mut re = URegularExpression::new(r"\d+", &[])?;
+        assert!(re.looking_at("12ab")?);
+        assert!(!re.looking_at("ab12")?);
+        Ok(())
+    }
+
+    #[test]
+    fn transparent_bounds() -> Result<(), common::Error> {
+        let mut re = URegularExpression::new(r"\bcd", &[])?;
+        re.set_region(2..4);
+        // With opaque bounds, the word boundary is at the start of the region.
+        assert!(re.is_match("abcd")?);
+        re.use_transparent_bounds(true);
+        assert!(!re.is_match("abcd")?);
+        Ok(())
+    }
+}
@@ -0,0 +1,503 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ICU resource bundle support for rust
+//!
+//! This crate provides access to ICU resource bundles, both the ones that ICU itself uses for
+//! its locale data, and application specific ones.  Specifically the functionality exposed
+//! through its C API, as available in the [header
+//! `ures.h`](https://unicode-org.github.io/icu-docs/apidoc/released/icu4c/ures_8h.html).
+//!
+//! A resource bundle is a tree: tables and arrays contain further resources, down to strings,
+//! integers and binary data at the leaves.  Each [UResourceBundle] is one node of the tree.
+//!
+//! Application resource bundles are usually packaged into a `.dat` file, which can be embedded
+//! into the binary and registered with `rust_icu_udata::set_app_data`.
+//!
+//! Are you missing some features from this crate?  Consider [reporting an
+//! issue](https://github.com/google/rust_icu/issues) or even [contributing the
+//! functionality](https://github.com/google/rust_icu/pulls).
+//!
+//! ## Examples
+//!
+//! Reading ICU's own locale data.
+//!
+//! ```
+//! use rust_icu_ures as ures;
+//! use rust_icu_uloc as uloc;
+//! use std::convert::TryFrom;
+//!
+//! let loc = uloc::ULoc::try_from("en").expect("locale");
+//! let bundle = ures::UResourceBundle::try_new(None, &loc).expect("bundle");
+//! let months = bundle
+//!     .get_by_path("calendar/gregorian/monthNames/format/wide")
+//!     .expect("months");
+//! assert_eq!(12, months.len());
+//! assert_eq!("January", months.get_string_by_index(0).expect("month"));
+//! ```
+
+use {
+    rust_icu_common as common, rust_icu_sys as sys,
+    rust_icu_sys::versioned_function,
+    rust_icu_sys::*,
+    rust_icu_uloc as uloc, rust_icu_ustring as ustring,
+    std::convert::TryFrom,
+    std::{ffi, os::raw, ptr, slice},
+};
+
+/// A node in the tree of a resource bundle.  The root of the tree is opened with
+/// [try_new](UResourceBundle::try_new), the other nodes are reached from it.
+///
+/// Implements `UResourceBundle` from ICU.
+#[derive(Debug)]
+pub struct UResourceBundle {
+    rep: ptr::NonNull<sys::UResourceBundle>,
+    // The warning reported by ICU when this resource was opened, if any.
+    warning: Option<sys::UErrorCode>,
+}
+
+impl Drop for UResourceBundle {
+    /// Implements `ures_close`
+    fn drop(&mut self) {
+        unsafe { versioned_function!(ures_close)(self.rep.as_ptr()) };
+    }
+}
+
+impl UResourceBundle {
+    /// Opens the resource bundle for `locale` from the package `package`, or from the ICU data
+    /// if `package` is `None`.  Resources that are missing for `locale` are looked up in its
+    /// parent locales, up to the root locale.
+    ///
+    /// Implements `ures_open`
+    pub fn try_new(
+        package: Option<&str>,
+        locale: &uloc::ULoc,
+    ) -> Result<UResourceBundle, common::Error> {
+        UResourceBundle::open("ures_open", versioned_function!(ures_open), package, locale)
+    }
+
+    /// Opens the resource bundle for `locale` from the package `package`, or from the ICU data
+    /// if `package` is `None`.  Unlike [try_new](UResourceBundle::try_new), does not fall
+    /// back to parent locales, so that the bundle must exist for exactly `locale`.
+    ///
+    /// Implements `ures_openDirect`
+    pub fn try_new_direct(
+        package: Option<&str>,
+        locale: &uloc::ULoc,
+    ) -> Result<UResourceBundle, common::Error> {
+        UResourceBundle::open(
+            "ures_openDirect",
+            versioned_function!(ures_openDirect),
+            package,
+            locale,
+        )
+    }
+
+    // Calls one of the ures_open* functions that take a package name and a locale.
+    fn open(
+        function: &'static str,
+        method: unsafe extern "C" fn(
+            *const raw::c_char,
+            *const raw::c_char,
+            *mut sys::UErrorCode,
+        ) -> *mut sys::UResourceBundle,
+        package: Option<&str>,
+        locale: &uloc::ULoc,
+    ) -> Result<UResourceBundle, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let asciiz_package = package.map(ffi::CString::new).transpose()?;
+        let asciiz_locale = locale.as_c_str();
+        // Requires that the package name, if any, and the locale are valid C strings, which
+        // they are by construction.
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            method(
+                asciiz_package
+                    .as_ref()
+                    .map_or(ptr::null(), |package| package.as_ptr()),
+                asciiz_locale.as_ptr(),
+                &mut status,
+            )
+        };
+        let warning = common::Error::ok_with_warning(function, status)?;
+        UResourceBundle::from_raw(function, rep, warning)
+    }
+
+    // Takes ownership of a resource bundle returned by the ICU function `function`, which
+    // reported `warning`.
+    fn from_raw(
+        function: &'static str,
+        rep: *mut sys::UResourceBundle,
+        warning: Option<sys::UErrorCode>,
+    ) -> Result<UResourceBundle, common::Error> {
+        match ptr::NonNull::new(rep) {
+            Some(rep) => Ok(UResourceBundle { rep, warning }),
+            None => Err(common::Error::MissingResource {
+                function,
+                code: UErrorCode::U_MISSING_RESOURCE_ERROR,
+            }),
+        }
+    }
+
+    /// Returns the locale that the data of this bundle actually comes from, which may be a
+    /// parent of the requested locale.
+    ///
+    /// Implements `ures_getLocaleByType`
+    pub fn get_locale(&self) -> Result<uloc::ULoc, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        // Requires that the returned string lives as long as self, which ICU guarantees.
+        let locale = unsafe {
+            assert!(common::Error::is_ok(status));
+            let locale = versioned_function!(ures_getLocaleByType)(
+                self.rep.as_ptr(),
+                sys::ULocDataLocaleType::ULOC_ACTUAL_LOCALE,
+                &mut status,
+            );
+            common::Error::ok_or_warning("ures_getLocaleByType", status)?;
+            ffi::CStr::from_ptr(locale)
+        };
+        uloc::ULoc::try_from(locale)
+    }
+
+    /// Returns the warning that ICU reported when this resource was opened, if any.  For
+    /// example, `U_USING_FALLBACK_WARNING` if the bundle comes from a parent of the requested
+    /// locale, or `U_USING_DEFAULT_WARNING` if it comes from the root locale or the default
+    /// locale.
+    pub fn warning(&self) -> Option<sys::UErrorCode> {
+        self.warning
+    }
+
+    /// Returns the key of this resource within its parent table, or `None` if this resource
+    /// is not in a table.
+    ///
+    /// Implements `ures_getKey`
+    pub fn get_key(&self) -> Option<String> {
+        // Requires that the returned string lives as long as self, which ICU guarantees.
+        unsafe {
+            let key = versioned_function!(ures_getKey)(self.rep.as_ptr());
+            key.as_ref()
+                .map(|key| ffi::CStr::from_ptr(key).to_string_lossy().into_owned())
+        }
+    }
+
+    /// Returns the type of this resource, such as `URES_TABLE` or `URES_STRING`.
+    ///
+    /// Implements `ures_getType`
+    pub fn get_type(&self) -> sys::UResType {
+        unsafe { versioned_function!(ures_getType)(self.rep.as_ptr()) }
+    }
+
+    /// Returns the number of resources in this table or array.  Other resources have a size of
+    /// 1.
+    ///
+    /// Implements `ures_getSize`
+    pub fn len(&self) -> usize {
+        unsafe { versioned_function!(ures_getSize)(self.rep.as_ptr()) as usize }
+    }
+
+    /// Returns true if this table or array contains no resources.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the resource with the key `key` from this table.
+    ///
+    /// Implements `ures_getByKey`
+    pub fn get_by_key(&self, key: &str) -> Result<UResourceBundle, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let asciiz_key = ffi::CString::new(key)?;
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ures_getByKey)(
+                self.rep.as_ptr(),
+                asciiz_key.as_ptr(),
+                ptr::null_mut(),
+                &mut status,
+            )
+        };
+        let warning = common::Error::ok_with_warning("ures_getByKey", status)?;
+        UResourceBundle::from_raw("ures_getByKey", rep, warning)
+    }
+
+    /// Returns the resource at `index` from this table or array.
+    ///
+    /// Implements `ures_getByIndex`
+    pub fn get_by_index(&self, index: usize) -> Result<UResourceBundle, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let rep = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ures_getByIndex)(
+                self.rep.as_ptr(),
+                index as i32,
+                ptr::null_mut(),
+                &mut status,
+            )
+        };
+        let warning = common::Error::ok_with_warning("ures_getByIndex", status)?;
+        UResourceBundle::from_raw("ures_getByIndex", rep, warning)
+    }
+
+    /// Returns the resource at `path`, which is a list of keys separated by `/`, such as
+    /// `calendar/gregorian/monthNames`.  Array elements are given by their index, as in
+    /// `colors/0`.
+    ///
+    /// Implements `ures_getByKey` and `ures_getByIndex`
+    pub fn get_by_path(&self, path: &str) -> Result<UResourceBundle, common::Error> {
+        let mut keys = path.split('/');
+        let first = keys.next().unwrap_or_default();
+        let mut resource = self.get_by_key_or_index(first)?;
+        for key in keys {
+            resource = resource.get_by_key_or_index(key)?;
+        }
+        Ok(resource)
+    }
+
+    // Arrays are indexed by number, everything else by key.
+    fn get_by_key_or_index(&self, key: &str) -> Result<UResourceBundle, common::Error> {
+        match (self.get_type(), key.parse::<usize>()) {
+            (sys::UResType::URES_ARRAY, Ok(index)) => self.get_by_index(index),
+            _ => self.get_by_key(key),
+        }
+    }
+
+    /// Iterates over the resources in this table or array.  The resources of a table are
+    /// returned in the order of their keys.
+    ///
+    /// Implements `ures_getByIndex`
+    pub fn iter(&self) -> impl Iterator<Item = Result<UResourceBundle, common::Error>> + '_ {
+        (0..self.len()).map(move |index| self.get_by_index(index))
+    }
+
+    /// Returns the contents of this string resource.
+    ///
+    /// Implements `ures_getString`
+    pub fn get_string(&self) -> Result<String, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let mut len: i32 = 0;
+        let raw = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ures_getString)(self.rep.as_ptr(), &mut len, &mut status)
+        };
+        common::Error::ok_or_warning("ures_getString", status)?;
+        // Requires that raw points to len valid characters, which ICU guarantees.
+        unsafe { string_from_raw(raw, len) }
+    }
+
+    /// Returns the contents of the string resource with the key `key` from this table.
+    ///
+    /// Implements `ures_getStringByKey`
+    pub fn get_string_by_key(&self, key: &str) -> Result<String, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let mut len: i32 = 0;
+        let asciiz_key = ffi::CString::new(key)?;
+        let raw = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ures_getStringByKey)(
+                self.rep.as_ptr(),
+                asciiz_key.as_ptr(),
+                &mut len,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("ures_getStringByKey", status)?;
+        // Requires that raw points to len valid characters, which ICU guarantees.
+        unsafe { string_from_raw(raw, len) }
+    }
+
+    /// Returns the contents of the string resource at `index` from this table or array.
+    ///
+    /// Implements `ures_getStringByIndex`
+    pub fn get_string_by_index(&self, index: usize) -> Result<String, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let mut len: i32 = 0;
+        let raw = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ures_getStringByIndex)(
+                self.rep.as_ptr(),
+                index as i32,
+                &mut len,
+                &mut status,
+            )
+        };
+        common::Error::ok_or_warning("ures_getStringByIndex", status)?;
+        // Requires that raw points to len valid characters, which ICU guarantees.
+        unsafe { string_from_raw(raw, len) }
+    }
+
+    /// Returns the value of this integer resource, as a signed number.
+    ///
+    /// Implements `ures_getInt`
+    pub fn get_int(&self) -> Result<i32, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let value = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ures_getInt)(self.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("ures_getInt", status)?;
+        Ok(value)
+    }
+
+    /// Returns the value of this integer resource, as an unsigned number.
+    ///
+    /// Implements `ures_getUInt`
+    pub fn get_uint(&self) -> Result<u32, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let value = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ures_getUInt)(self.rep.as_ptr(), &mut status)
+        };
+        common::Error::ok_or_warning("ures_getUInt", status)?;
+        Ok(value)
+    }
+
+    /// Returns the contents of this integer vector resource.
+    ///
+    /// Implements `ures_getIntVector`
+    pub fn get_int_vector(&self) -> Result<Vec<i32>, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let mut len: i32 = 0;
+        let raw = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ures_getIntVector)(self.rep.as_ptr(), &mut len, &mut status)
+        };
+        common::Error::ok_or_warning("ures_getIntVector", status)?;
+        // Requires that raw points to len valid integers, which ICU guarantees.
+        Ok(unsafe { slice_from_raw(raw, len) }.to_vec())
+    }
+
+    /// Returns the contents of this binary resource.
+    ///
+    /// Implements `ures_getBinary`
+    pub fn get_binary(&self) -> Result<Vec<u8>, common::Error> {
+        let mut status = common::Error::OK_CODE;
+        let mut len: i32 = 0;
+        let raw = unsafe {
+            assert!(common::Error::is_ok(status));
+            versioned_function!(ures_getBinary)(self.rep.as_ptr(), &mut len, &mut status)
+        };
+        common::Error::ok_or_warning("ures_getBinary", status)?;
+        // Requires that raw points to len valid bytes, which ICU guarantees.
+        Ok(unsafe { slice_from_raw(raw, len) }.to_vec())
+    }
+}
+
+// Makes a slice out of a pointer and length returned by ICU, which does not return a valid
+// pointer for empty data.
+unsafe fn slice_from_raw<'a, T>(raw: *const T, len: i32) -> &'a [T] {
+    if raw.is_null() || len <= 0 {
+        return &[];
+    }
+    slice::from_raw_parts(raw, len as usize)
+}
+
+// Copies out a string of len characters from raw, which is owned by ICU.
+unsafe fn string_from_raw(raw: *const sys::UChar, len: i32) -> Result<String, common::Error> {
+    let chars = slice_from_raw(raw, len);
+    let mut text = ustring::UChar::new_with_capacity(chars.len());
+    ptr::copy_nonoverlapping(chars.as_ptr(), text.as_mut_c_ptr(), chars.len());
+    String::try_from(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rust_icu_udata as udata, sys::UResType::*};
+
+    // ICU data must be suitably aligned.
+    #[repr(C, align(16))]
+    struct Aligned<T: ?Sized>(T);
+
+    // The resource bundles from testdata/root.txt and testdata/de.txt.
+    static TESTDATA: &Aligned<[u8]> = &Aligned(*include_bytes!("../testdata/testdata.dat"));
+
+    fn open_testdata(locale: &str) -> Result<UResourceBundle, common::Error> {
+        // The test data is a complete ICU common data file, aligned to 16 bytes.
+        unsafe { udata::set_app_data("testdata", &TESTDATA.0)? };
+        UResourceBundle::try_new(Some("testdata"), &uloc::ULoc::try_from(locale)?)
+    }
+
+    #[test]
+    fn locale_fallback() -> Result<(), common::Error> {
+        let bundle = open_testdata("de-AT")?;
+        assert_eq!(uloc::ULoc::try_from("de")?, bundle.get_locale()?);
+        assert_eq!(Some(UErrorCode::U_USING_FALLBACK_WARNING), bundle.warning());
+        assert_eq!("Hallo", bundle.get_string_by_key("greeting")?);
+        // Only in the root bundle.
+        assert_eq!("S", bundle.get_by_path("sizes/small")?.get_string()?);
+
+        let bundle = open_testdata("fr")?;
+        assert_eq!("Hello", bundle.get_string_by_key("greeting")?);
+        assert_eq!(Some(UErrorCode::U_USING_DEFAULT_WARNING), bundle.warning());
+        assert_eq!(None, open_testdata("de")?.warning());
+
+        let loc = uloc::ULoc::try_from("fr")?;
+        assert!(UResourceBundle::try_new_direct(Some("testdata"), &loc).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn resource_types() -> Result<(), common::Error> {
+        let bundle = open_testdata("root")?;
+        assert_eq!(URES_TABLE, bundle.get_type());
+
+        let count = bundle.get_by_key("count")?;
+        assert_eq!(URES_INT, count.get_type());
+        assert_eq!(42, count.get_int()?);
+        assert_eq!(42, count.get_uint()?);
+
+        let digits = bundle.get_by_key("digits")?;
+        assert_eq!(URES_INT_VECTOR, digits.get_type());
+        assert_eq!(vec![1, 2, 3], digits.get_int_vector()?);
+
+        let colors = bundle.get_by_key("colors")?;
+        assert_eq!(URES_ARRAY, colors.get_type());
+        assert_eq!(3, colors.len());
+        assert_eq!("green", colors.get_string_by_index(1)?);
+        assert_eq!("blue", bundle.get_by_path("colors/2")?.get_string()?);
+
+        assert!(bundle.get_by_key("nonexistent").is_err());
+        assert!(count.get_string().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn iterate_table() -> Result<(), common::Error> {
+        let sizes = open_testdata("root")?.get_by_key("sizes")?;
+        assert_eq!(Some("sizes".to_string()), sizes.get_key());
+        let entries = sizes
+            .iter()
+            .map(|entry| {
+                let entry = entry?;
+                Ok((entry.get_key().unwrap(), entry.get_string()?))
+            })
+            .collect::<Result<Vec<(String, String)>, common::Error>>()?;
+        assert_eq!(
+            vec![
+                ("large".to_string(), "L".to_string()),
+                ("small".to_string(), "S".to_string()),
+            ],
+            entries
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn icu_data() -> Result<(), common::Error> {
+        let loc = uloc::ULoc::try_from("de")?;
+        let bundle = UResourceBundle::try_new(None, &loc)?;
+        let days = bundle.get_by_path("calendar/gregorian/dayNames/format/wide")?;
+        assert_eq!(7, days.len());
+        assert_eq!("Sonntag", days.get_string_by_index(0)?);
+        Ok(())
+    }
+}
@@ -70,7 +70,7 @@ impl TryFrom<&str> for crate::UChar {
         }
         trace!("before error check");
         // We expect buffer overflow error here.  The API is weird, but there you go.
-        common::Error::ok_preflight(status)?;
+        common::Error::ok_preflight("u_strFromUTF8", status)?;
         trace!("input  utf8->UChar*: {:?}", rust_string);
         let mut rep: Vec<sys::UChar> = vec![0; dest_length as usize];
         let mut status = common::Error::OK_CODE;
@@ -87,7 +87,7 @@ impl TryFrom<&str> for crate::UChar {
                 &mut status,
             );
         }
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("u_strFromUTF8", status)?;
         trace!("result utf8->uchar*[{}]: {:?}", dest_length, rep);
         Ok(crate::UChar { rep })
     }
@@ -122,7 +122,7 @@ impl TryFrom<&UChar> for String {
         // The API doesn't really document this well, but the preflight code will report buffer
         // overflow error even when we are explicitly just trying to check for the size of the
         // resulting buffer.
-        common::Error::ok_preflight(status)?;
+        common::Error::ok_preflight("u_strToUTF8", status)?;
 
         // Buffer to store the converted string.
         let mut buf: Vec<u8> = vec![0; dest_length as usize];
@@ -143,7 +143,7 @@ impl TryFrom<&UChar> for String {
             );
         }
         trace!("post: result UChar*->utf8 buf[{}]: {:?}", buf.len(), buf);
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("u_strToUTF8", status)?;
         let s = String::from_utf8(buf);
         match s {
             Err(e) => Err(e.into()),
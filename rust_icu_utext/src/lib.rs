@@ -64,6 +64,11 @@ impl TryFrom<&str> for Text {
 }
 
 impl Text {
+    /// Internal representation, for ICU4C methods that require it.
+    pub fn repr(&mut self) -> *mut sys::UText {
+        self.rep
+    }
+
     /// Constructs the Text from raw byte contents.
     ///
     /// The expectation is that the buffer and length are valid and compatible.  That is,
@@ -73,7 +78,7 @@ impl Text {
         let mut status = common::Error::OK_CODE;
         // Requires that 'bytes' is a valid pointer and len is the correct length of 'bytes'.
         let rep = versioned_function!(utext_openUTF8)(0 as *mut UText, buffer, len, &mut status);
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("utext_openUTF8", status)?;
         Ok(Text { rep })
     }
 
@@ -96,7 +101,7 @@ impl Text {
                 &mut status,
             )
         };
-        common::Error::ok_or_warning(status)?;
+        common::Error::ok_or_warning("utext_clone", status)?;
         Ok(Text { rep })
     }
 }